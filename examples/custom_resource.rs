@@ -1,9 +1,9 @@
 use std::env;
 
 use pokemon_tcg_api_client::{
-    builder::{pokemon::PokemonQueryBuilder, QueryBuilder},
+    builder::{field::CardField, pokemon::PokemonQueryBuilder, FilterValue, QueryBuilder},
     pokemon_api_client::{
-        api_client::{CardId, PokemonApiClient, API_URL},
+        api_client::{CardId, PokemonApiClient},
         error::ApiError,
     },
     resource::ApiResource,
@@ -16,8 +16,8 @@ pub struct BulbId {
 }
 
 impl ApiResource for BulbId {
-    fn url() -> String {
-        format!("{API_URL}/cards")
+    fn path() -> String {
+        String::from("/cards")
     }
 }
 
@@ -30,9 +30,9 @@ async fn main() -> Result<(), ApiError> {
     let bulbs_ids = api_client
         .get_queryable_resources::<Vec<BulbId>, PokemonQueryBuilder>({
             PokemonQueryBuilder::new()
-                .add_name("bulb*")
-                .add_select("id")
-                .add_select("name")
+                .add_name(FilterValue::wildcard("bulb*"))
+                .add_select(CardField::Id)
+                .add_select(CardField::Name)
                 .with_page_size(10)
                 .clone()
         })
@@ -1,7 +1,7 @@
 use std::env;
 
 use pokemon_tcg_api_client::{
-    builder::{pokemon::PokemonQueryBuilder, QueryBuilder},
+    builder::{pokemon::PokemonQueryBuilder, FilterValue, QueryBuilder},
     pokemon_api_client::{api_client::PokemonApiClient, error::ApiError},
     resource::card::Card,
 };
@@ -15,7 +15,7 @@ async fn main() -> Result<(), ApiError> {
     let bulbs = api_client
         .get_queryable_resources::<Vec<Card>, PokemonQueryBuilder>(
             PokemonQueryBuilder::new()
-                .add_name("bulb*")
+                .add_name(FilterValue::wildcard("bulb*"))
                 .with_page_size(10),
         )
         .await?;
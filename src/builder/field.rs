@@ -0,0 +1,499 @@
+use std::fmt::{self, Display};
+
+use super::Ordering;
+
+/// Fields of cards, used for selecting, ordering and filtering in the card query builders.
+/// Fields unknown to this crate can be used with `CardField::Raw`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum CardField {
+    Id,
+    Name,
+    Supertype,
+    Subtypes,
+    Hp,
+    Types,
+    EvolvesFrom,
+    EvolvesTo,
+    Rules,
+    AncientTrait,
+    AncientTraitName,
+    AncientTraitText,
+    Abilities,
+    AbilityName,
+    AbilityText,
+    AbilityType,
+    Attacks,
+    AttackName,
+    AttackCost,
+    AttackConvertedEnergyCost,
+    AttackDamage,
+    AttackText,
+    Weaknesses,
+    WeaknessType,
+    WeaknessValue,
+    Resistances,
+    ResistanceType,
+    ResistanceValue,
+    RetreatCost,
+    ConvertedRetreatCost,
+    /// The whole set of the card.
+    Set,
+    /// A field of the set of the card, e.g. `set.releaseDate`.
+    SetField(SetField),
+    Number,
+    Artist,
+    Rarity,
+    FlavorText,
+    NationalPokedexNumbers,
+    Legalities,
+    LegalitiesStandard,
+    LegalitiesExpanded,
+    LegalitiesUnlimited,
+    RegulationMark,
+    Images,
+    ImagesSmall,
+    ImagesLarge,
+    Tcgplayer,
+    TcgplayerUrl,
+    TcgplayerUpdatedAt,
+    TcgplayerPrices,
+    /// A price of a variant on TCGplayer, e.g. `tcgplayer.prices.holofoil.market`.
+    TcgplayerPrice(TcgplayerVariant, TcgplayerPriceKind),
+    Cardmarket,
+    CardmarketUrl,
+    CardmarketUpdatedAt,
+    CardmarketPrices,
+    /// A price on Cardmarket, e.g. `cardmarket.prices.trendPrice`.
+    CardmarketPrice(CardmarketPriceKind),
+    /// Any other field, given by its json path.
+    Raw(String),
+}
+
+/// Fields of sets, used for selecting, ordering and filtering in the set query builder.
+/// Fields unknown to this crate can be used with `SetField::Raw`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum SetField {
+    Id,
+    Name,
+    Series,
+    PrintedTotal,
+    Total,
+    Legalities,
+    LegalitiesStandard,
+    LegalitiesExpanded,
+    LegalitiesUnlimited,
+    PtcgoCode,
+    ReleaseDate,
+    UpdatedAt,
+    Images,
+    ImagesSymbol,
+    ImagesLogo,
+    /// Any other field, given by its json path.
+    Raw(String),
+}
+
+/// Variants of a card priced on TCGplayer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TcgplayerVariant {
+    Normal,
+    Holofoil,
+    ReverseHolofoil,
+    FirstEditionHolofoil,
+    FirstEditionNormal,
+}
+
+/// Prices of a variant on TCGplayer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TcgplayerPriceKind {
+    Low,
+    Mid,
+    High,
+    Market,
+    DirectLow,
+}
+
+/// Prices of a card on Cardmarket.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CardmarketPriceKind {
+    AverageSellPrice,
+    LowPrice,
+    TrendPrice,
+    GermanProLow,
+    SuggestedPrice,
+    ReverseHoloSell,
+    ReverseHoloLow,
+    ReverseHoloTrend,
+    LowPriceExPlus,
+    Avg1,
+    Avg7,
+    Avg30,
+    ReverseHoloAvg1,
+    ReverseHoloAvg7,
+    ReverseHoloAvg30,
+}
+
+/// Card fields which are not composed of other fields.
+static CARD_FIELDS: &[CardField] = &[
+    CardField::Id,
+    CardField::Name,
+    CardField::Supertype,
+    CardField::Subtypes,
+    CardField::Hp,
+    CardField::Types,
+    CardField::EvolvesFrom,
+    CardField::EvolvesTo,
+    CardField::Rules,
+    CardField::AncientTrait,
+    CardField::AncientTraitName,
+    CardField::AncientTraitText,
+    CardField::Abilities,
+    CardField::AbilityName,
+    CardField::AbilityText,
+    CardField::AbilityType,
+    CardField::Attacks,
+    CardField::AttackName,
+    CardField::AttackCost,
+    CardField::AttackConvertedEnergyCost,
+    CardField::AttackDamage,
+    CardField::AttackText,
+    CardField::Weaknesses,
+    CardField::WeaknessType,
+    CardField::WeaknessValue,
+    CardField::Resistances,
+    CardField::ResistanceType,
+    CardField::ResistanceValue,
+    CardField::RetreatCost,
+    CardField::ConvertedRetreatCost,
+    CardField::Set,
+    CardField::Number,
+    CardField::Artist,
+    CardField::Rarity,
+    CardField::FlavorText,
+    CardField::NationalPokedexNumbers,
+    CardField::Legalities,
+    CardField::LegalitiesStandard,
+    CardField::LegalitiesExpanded,
+    CardField::LegalitiesUnlimited,
+    CardField::RegulationMark,
+    CardField::Images,
+    CardField::ImagesSmall,
+    CardField::ImagesLarge,
+    CardField::Tcgplayer,
+    CardField::TcgplayerUrl,
+    CardField::TcgplayerUpdatedAt,
+    CardField::TcgplayerPrices,
+    CardField::Cardmarket,
+    CardField::CardmarketUrl,
+    CardField::CardmarketUpdatedAt,
+    CardField::CardmarketPrices,
+];
+
+static SET_FIELDS: &[SetField] = &[
+    SetField::Id,
+    SetField::Name,
+    SetField::Series,
+    SetField::PrintedTotal,
+    SetField::Total,
+    SetField::Legalities,
+    SetField::LegalitiesStandard,
+    SetField::LegalitiesExpanded,
+    SetField::LegalitiesUnlimited,
+    SetField::PtcgoCode,
+    SetField::ReleaseDate,
+    SetField::UpdatedAt,
+    SetField::Images,
+    SetField::ImagesSymbol,
+    SetField::ImagesLogo,
+];
+
+static TCGPLAYER_VARIANTS: &[TcgplayerVariant] = &[
+    TcgplayerVariant::Normal,
+    TcgplayerVariant::Holofoil,
+    TcgplayerVariant::ReverseHolofoil,
+    TcgplayerVariant::FirstEditionHolofoil,
+    TcgplayerVariant::FirstEditionNormal,
+];
+
+static TCGPLAYER_PRICE_KINDS: &[TcgplayerPriceKind] = &[
+    TcgplayerPriceKind::Low,
+    TcgplayerPriceKind::Mid,
+    TcgplayerPriceKind::High,
+    TcgplayerPriceKind::Market,
+    TcgplayerPriceKind::DirectLow,
+];
+
+static CARDMARKET_PRICE_KINDS: &[CardmarketPriceKind] = &[
+    CardmarketPriceKind::AverageSellPrice,
+    CardmarketPriceKind::LowPrice,
+    CardmarketPriceKind::TrendPrice,
+    CardmarketPriceKind::GermanProLow,
+    CardmarketPriceKind::SuggestedPrice,
+    CardmarketPriceKind::ReverseHoloSell,
+    CardmarketPriceKind::ReverseHoloLow,
+    CardmarketPriceKind::ReverseHoloTrend,
+    CardmarketPriceKind::LowPriceExPlus,
+    CardmarketPriceKind::Avg1,
+    CardmarketPriceKind::Avg7,
+    CardmarketPriceKind::Avg30,
+    CardmarketPriceKind::ReverseHoloAvg1,
+    CardmarketPriceKind::ReverseHoloAvg7,
+    CardmarketPriceKind::ReverseHoloAvg30,
+];
+
+impl CardField {
+    /// Returns the json path of the field, e.g. `set.releaseDate`.
+    #[must_use]
+    pub fn path(&self) -> String {
+        self.to_string()
+    }
+
+    /// Returns the field for a json path, unknown paths result in `CardField::Raw`.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The json path of the field, e.g. `tcgplayer.prices.holofoil.market`.
+    #[must_use]
+    pub fn from_path(path: &str) -> Self {
+        let known = if let Some(set_path) = path.strip_prefix("set.") {
+            match SetField::from_path(set_path) {
+                SetField::Raw(_) => None,
+                field => Some(CardField::SetField(field)),
+            }
+        } else if let Some(price_path) = path.strip_prefix("tcgplayer.prices.") {
+            price_path.split_once('.').and_then(|(variant, kind)| {
+                let variant = TCGPLAYER_VARIANTS
+                    .iter()
+                    .find(|known| known.name() == variant)?;
+                let kind = TCGPLAYER_PRICE_KINDS
+                    .iter()
+                    .find(|known| known.name() == kind)?;
+
+                Some(CardField::TcgplayerPrice(*variant, *kind))
+            })
+        } else if let Some(kind) = path.strip_prefix("cardmarket.prices.") {
+            CARDMARKET_PRICE_KINDS
+                .iter()
+                .find(|known| known.name() == kind)
+                .map(|kind| CardField::CardmarketPrice(*kind))
+        } else {
+            CARD_FIELDS
+                .iter()
+                .find(|known| known.simple_path() == Some(path))
+                .cloned()
+        };
+
+        known.unwrap_or_else(|| CardField::Raw(String::from(path)))
+    }
+
+    /// Returns the ascending ordering of the field.
+    #[must_use]
+    pub fn ascending(self) -> Ordering {
+        Ordering::Ascending(self.path())
+    }
+
+    /// Returns the descending ordering of the field.
+    #[must_use]
+    pub fn descending(self) -> Ordering {
+        Ordering::Descending(self.path())
+    }
+
+    /// Returns the path of fields which are not composed of other fields.
+    fn simple_path(&self) -> Option<&'static str> {
+        Some(match self {
+            CardField::Id => "id",
+            CardField::Name => "name",
+            CardField::Supertype => "supertype",
+            CardField::Subtypes => "subtypes",
+            CardField::Hp => "hp",
+            CardField::Types => "types",
+            CardField::EvolvesFrom => "evolvesFrom",
+            CardField::EvolvesTo => "evolvesTo",
+            CardField::Rules => "rules",
+            CardField::AncientTrait => "ancientTrait",
+            CardField::AncientTraitName => "ancientTrait.name",
+            CardField::AncientTraitText => "ancientTrait.text",
+            CardField::Abilities => "abilities",
+            CardField::AbilityName => "abilities.name",
+            CardField::AbilityText => "abilities.text",
+            CardField::AbilityType => "abilities.type",
+            CardField::Attacks => "attacks",
+            CardField::AttackName => "attacks.name",
+            CardField::AttackCost => "attacks.cost",
+            CardField::AttackConvertedEnergyCost => "attacks.convertedEnergyCost",
+            CardField::AttackDamage => "attacks.damage",
+            CardField::AttackText => "attacks.text",
+            CardField::Weaknesses => "weaknesses",
+            CardField::WeaknessType => "weaknesses.type",
+            CardField::WeaknessValue => "weaknesses.value",
+            CardField::Resistances => "resistances",
+            CardField::ResistanceType => "resistances.type",
+            CardField::ResistanceValue => "resistances.value",
+            CardField::RetreatCost => "retreatCost",
+            CardField::ConvertedRetreatCost => "convertedRetreatCost",
+            CardField::Set => "set",
+            CardField::Number => "number",
+            CardField::Artist => "artist",
+            CardField::Rarity => "rarity",
+            CardField::FlavorText => "flavorText",
+            CardField::NationalPokedexNumbers => "nationalPokedexNumbers",
+            CardField::Legalities => "legalities",
+            CardField::LegalitiesStandard => "legalities.standard",
+            CardField::LegalitiesExpanded => "legalities.expanded",
+            CardField::LegalitiesUnlimited => "legalities.unlimited",
+            CardField::RegulationMark => "regulationMark",
+            CardField::Images => "images",
+            CardField::ImagesSmall => "images.small",
+            CardField::ImagesLarge => "images.large",
+            CardField::Tcgplayer => "tcgplayer",
+            CardField::TcgplayerUrl => "tcgplayer.url",
+            CardField::TcgplayerUpdatedAt => "tcgplayer.updatedAt",
+            CardField::TcgplayerPrices => "tcgplayer.prices",
+            CardField::Cardmarket => "cardmarket",
+            CardField::CardmarketUrl => "cardmarket.url",
+            CardField::CardmarketUpdatedAt => "cardmarket.updatedAt",
+            CardField::CardmarketPrices => "cardmarket.prices",
+            CardField::SetField(_)
+            | CardField::TcgplayerPrice(_, _)
+            | CardField::CardmarketPrice(_)
+            | CardField::Raw(_) => return None,
+        })
+    }
+}
+
+impl SetField {
+    /// Returns the json path of the field, e.g. `legalities.standard`.
+    #[must_use]
+    pub fn path(&self) -> String {
+        self.to_string()
+    }
+
+    /// Returns the field for a json path, unknown paths result in `SetField::Raw`.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The json path of the field, e.g. `releaseDate`.
+    #[must_use]
+    pub fn from_path(path: &str) -> Self {
+        SET_FIELDS
+            .iter()
+            .find(|known| known.simple_path() == Some(path))
+            .cloned()
+            .unwrap_or_else(|| SetField::Raw(String::from(path)))
+    }
+
+    /// Returns the ascending ordering of the field.
+    #[must_use]
+    pub fn ascending(self) -> Ordering {
+        Ordering::Ascending(self.path())
+    }
+
+    /// Returns the descending ordering of the field.
+    #[must_use]
+    pub fn descending(self) -> Ordering {
+        Ordering::Descending(self.path())
+    }
+
+    fn simple_path(&self) -> Option<&'static str> {
+        Some(match self {
+            SetField::Id => "id",
+            SetField::Name => "name",
+            SetField::Series => "series",
+            SetField::PrintedTotal => "printedTotal",
+            SetField::Total => "total",
+            SetField::Legalities => "legalities",
+            SetField::LegalitiesStandard => "legalities.standard",
+            SetField::LegalitiesExpanded => "legalities.expanded",
+            SetField::LegalitiesUnlimited => "legalities.unlimited",
+            SetField::PtcgoCode => "ptcgoCode",
+            SetField::ReleaseDate => "releaseDate",
+            SetField::UpdatedAt => "updatedAt",
+            SetField::Images => "images",
+            SetField::ImagesSymbol => "images.symbol",
+            SetField::ImagesLogo => "images.logo",
+            SetField::Raw(_) => return None,
+        })
+    }
+}
+
+impl TcgplayerVariant {
+    /// Returns the json name of the variant.
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            TcgplayerVariant::Normal => "normal",
+            TcgplayerVariant::Holofoil => "holofoil",
+            TcgplayerVariant::ReverseHolofoil => "reverseHolofoil",
+            TcgplayerVariant::FirstEditionHolofoil => "1stEditionHolofoil",
+            TcgplayerVariant::FirstEditionNormal => "1stEditionNormal",
+        }
+    }
+}
+
+impl TcgplayerPriceKind {
+    /// Returns the json name of the price.
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            TcgplayerPriceKind::Low => "low",
+            TcgplayerPriceKind::Mid => "mid",
+            TcgplayerPriceKind::High => "high",
+            TcgplayerPriceKind::Market => "market",
+            TcgplayerPriceKind::DirectLow => "directLow",
+        }
+    }
+}
+
+impl CardmarketPriceKind {
+    /// Returns the json name of the price.
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            CardmarketPriceKind::AverageSellPrice => "averageSellPrice",
+            CardmarketPriceKind::LowPrice => "lowPrice",
+            CardmarketPriceKind::TrendPrice => "trendPrice",
+            CardmarketPriceKind::GermanProLow => "germanProLow",
+            CardmarketPriceKind::SuggestedPrice => "suggestedPrice",
+            CardmarketPriceKind::ReverseHoloSell => "reverseHoloSell",
+            CardmarketPriceKind::ReverseHoloLow => "reverseHoloLow",
+            CardmarketPriceKind::ReverseHoloTrend => "reverseHoloTrend",
+            CardmarketPriceKind::LowPriceExPlus => "lowPriceExPlus",
+            CardmarketPriceKind::Avg1 => "avg1",
+            CardmarketPriceKind::Avg7 => "avg7",
+            CardmarketPriceKind::Avg30 => "avg30",
+            CardmarketPriceKind::ReverseHoloAvg1 => "reverseHoloAvg1",
+            CardmarketPriceKind::ReverseHoloAvg7 => "reverseHoloAvg7",
+            CardmarketPriceKind::ReverseHoloAvg30 => "reverseHoloAvg30",
+        }
+    }
+}
+
+/// Formats the field as its json path.
+impl Display for CardField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CardField::SetField(field) => write!(f, "set.{field}"),
+            CardField::TcgplayerPrice(variant, kind) => {
+                write!(f, "tcgplayer.prices.{}.{}", variant.name(), kind.name())
+            }
+            CardField::CardmarketPrice(kind) => write!(f, "cardmarket.prices.{}", kind.name()),
+            CardField::Raw(path) => f.write_str(path),
+            field => f.write_str(field.simple_path().unwrap_or_default()),
+        }
+    }
+}
+
+/// Formats the field as its json path.
+impl Display for SetField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SetField::Raw(path) => f.write_str(path),
+            field => f.write_str(field.simple_path().unwrap_or_default()),
+        }
+    }
+}
+
+impl From<SetField> for CardField {
+    fn from(field: SetField) -> Self {
+        CardField::SetField(field)
+    }
+}
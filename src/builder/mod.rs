@@ -1,39 +1,254 @@
 //! Contains the implementation for the query builders used for advanced querying to the api.
 pub mod energy;
+pub mod field;
+pub mod parser;
 pub mod pokemon;
+pub mod query;
+pub mod saved;
 pub mod set;
 pub mod trainer;
 
-use std::{collections::HashMap, fmt::Display};
+use std::{collections::BTreeMap, fmt::Display};
+
+use self::{
+    parser::{parse_query, ParseError},
+    query::Query,
+};
 
 /// Container for the ordering of query fields of the results.
-#[derive(Clone)]
+/// Nested fields are separated by dots, e.g. `set.releaseDate`.
+/// Typed orderings are created with `CardField::ascending` or `SetField::descending` and the like.
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum Ordering {
     Ascending(String),
     Descending(String),
 }
 
+impl Ordering {
+    /// Returns the field which is ordered by.
+    #[must_use]
+    pub fn field(&self) -> &str {
+        match self {
+            Ordering::Ascending(field) | Ordering::Descending(field) => field,
+        }
+    }
+
+    /// Returns whether the field is ordered descending.
+    #[must_use]
+    pub fn is_descending(&self) -> bool {
+        matches!(self, Ordering::Descending(_))
+    }
+}
+
+/// Formats the ordering the way the api expects it in `orderBy`, descending fields are prefixed with `-`.
+impl Display for Ordering {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Ordering::Ascending(field) => write!(f, "{field}"),
+            Ordering::Descending(field) => write!(f, "-{field}"),
+        }
+    }
+}
+
+/// A single value of a filter, its type decides how the value is sent to the api.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FilterValue {
+    /// Text the field has to contain, special characters of the query syntax are escaped.
+    Text(String),
+    /// Pattern with `*` and `?` placeholders, all other special characters and whitespace are escaped.
+    Wildcard(String),
+    /// Text the field has to be exactly, special characters of the query syntax are escaped.
+    Exact(String),
+    /// Range between two bounds, `*` is unbounded.
+    Range {
+        low: String,
+        high: String,
+        inclusive: bool,
+    },
+    /// Value which is sent as is, e.g. for syntax of the api not covered by the other values.
+    Raw(String),
+}
+
+impl FilterValue {
+    /// Creates a pattern with `*` and `?` placeholders.
+    ///
+    /// # Arguments
+    ///
+    /// * `pattern` - The pattern the field has to match.
+    #[must_use]
+    pub fn wildcard(pattern: &str) -> Self {
+        FilterValue::Wildcard(String::from(pattern))
+    }
+
+    /// Creates a text the field has to be exactly.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The value the field has to be.
+    #[must_use]
+    pub fn exact(value: &str) -> Self {
+        FilterValue::Exact(String::from(value))
+    }
+
+    /// Creates a range between two bounds, use `*` for an unbounded side.
+    ///
+    /// # Arguments
+    ///
+    /// * `low` - The low bound of the range.
+    /// * `high` - The high bound of the range.
+    /// * `inclusive` - Sets whether the bounds are part of the range.
+    #[must_use]
+    pub fn range(low: &str, high: &str, inclusive: bool) -> Self {
+        FilterValue::Range {
+            low: String::from(low),
+            high: String::from(high),
+            inclusive,
+        }
+    }
+
+    /// Creates a value which is sent without escaping.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The value in the syntax of the api.
+    #[must_use]
+    pub fn raw(value: &str) -> Self {
+        FilterValue::Raw(String::from(value))
+    }
+
+    /// Returns the query matching this value in the field.
+    ///
+    /// # Arguments
+    ///
+    /// * `field` - The field to filter.
+    #[must_use]
+    pub fn to_query(&self, field: &str) -> Query {
+        match self {
+            FilterValue::Text(value) => Query::term(field, value),
+            FilterValue::Wildcard(pattern) => Query::wildcard(field, pattern),
+            FilterValue::Exact(value) => Query::exact(field, value),
+            FilterValue::Range {
+                low,
+                high,
+                inclusive,
+            } => Query::range(field, low, high, *inclusive),
+            FilterValue::Raw(value) => Query::raw(field, value),
+        }
+    }
+}
+
+impl From<&str> for FilterValue {
+    fn from(value: &str) -> Self {
+        FilterValue::Text(String::from(value))
+    }
+}
+
+impl From<String> for FilterValue {
+    fn from(value: String) -> Self {
+        FilterValue::Text(value)
+    }
+}
+
 /// Trait for implementing query builders for the api client.
 pub trait QueryBuilder {
     /// Creates a new instance of the query builder.
     fn new() -> Self;
+    /// Sets the page size of the query builder.
+    ///
+    /// # Arguments
+    ///
+    /// * `size` - The size of the page.
+    #[must_use]
+    fn with_page_size(self, size: u8) -> Self;
+    /// Sets the page from which it should get the results.
+    ///
+    /// # Arguments
+    ///
+    /// * `page` - The number of the page to select.
+    #[must_use]
+    fn with_page(self, page: u32) -> Self;
     /// Returns the current page the query builder is set to.
     fn page(&self) -> Option<u32>;
     /// Returns the current page size the query builder is set to.
     fn page_size(&self) -> Option<u8>;
     /// Returns the filters set for the query builder.
-    fn filters(&self) -> HashMap<String, String>;
+    fn filters(&self) -> BTreeMap<String, Vec<FilterValue>>;
+    /// Returns the filters of values which are excluded from the results.
+    fn excluded_filters(&self) -> BTreeMap<String, Vec<FilterValue>>;
     /// Returns the ordering information of the query builder.
     fn order_by(&self) -> Vec<Ordering>;
     /// Returns the selected fields for the returning json of the query builder.
     fn select_fields(&self) -> Vec<String>;
+    /// Returns the query expression which has to match in addition to the filters.
+    fn query(&self) -> Option<Query>;
+    /// Adds a query expression which has to match in addition to the filters.
+    /// If used more than once all expressions have to match.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - The query expression to add.
+    #[must_use]
+    fn with_query(self, query: Query) -> Self;
+
+    /// Creates a query builder from a query string in the syntax of the api, e.g. `name:char* types:fire hp:[100 TO *]`.
+    /// The parsed query is kept as query expression, so building the query builder renders it again.
+    /// An empty query string creates a query builder without any query.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The query string to parse.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the query string is malformed, with the span of the malformed part.
+    fn parse(input: &str) -> Result<Self, ParseError>
+    where
+        Self: Sized,
+    {
+        if input.trim().is_empty() {
+            return Ok(Self::new());
+        }
+
+        Ok(Self::new().with_query(parse_query(input)?))
+    }
+
+    /// Returns the filters and the query expression combined into a single query,
+    /// `None` if the query builder does not filter at all.
+    /// Repeated values of a filter are combined with an OR, different filters with an AND.
+    /// Every excluded value is negated on its own and combined with an AND.
+    /// Filters are ordered by their field and values, so equal query builders always produce the same query.
+    fn to_query(&self) -> Option<Query> {
+        let mut queries = self
+            .filters()
+            .iter()
+            .filter_map(|(field, values)| Query::any(filter_queries(field, values)))
+            .collect::<Vec<Query>>();
+
+        queries.extend(
+            self.excluded_filters()
+                .iter()
+                .flat_map(|(field, values)| filter_queries(field, values))
+                .map(Query::not),
+        );
+
+        queries.extend(self.query());
+
+        Query::all(queries)
+    }
+
+    /// Returns a key identifying the query of the query builder, e.g. for caching or logging.
+    /// The key is the canonical query string built by `build`, so equal queries have equal keys.
+    fn canonical_key(&self) -> String {
+        self.build("").trim_start_matches('?').to_owned()
+    }
 
     /// Builds and returns the query url for the api request.
+    /// The url is canonical: selected fields are sorted and filters are ordered and normalized,
+    /// so equal query builders always produce the same url.
     ///
     /// * `url` - The base url of the query url.
     fn build(&self, url: &str) -> String {
-        let url = url;
-        let mut query_index = url.find(|x| x == '?');
+        let mut query_index = url.find('?');
 
         let mut builder = String::from(url);
 
@@ -47,73 +262,43 @@ pub trait QueryBuilder {
         let order_by = self.order_by();
 
         if !order_by.is_empty() {
-            todo!()
+            let order_by_query = order_by
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<String>>()
+                .join(",");
+
+            build_query_param(&mut builder, &mut query_index, "orderBy", order_by_query);
         }
 
-        let fields = self.select_fields();
+        let mut fields = self.select_fields();
+        fields.sort();
+        fields.dedup();
 
         if !fields.is_empty() {
-            let select_query = fields
-                .iter()
-                .map(|x| format!("{x},"))
-                .collect::<Vec<String>>()
-                .concat()
-                .trim_end_matches(',')
-                .to_owned();
+            let select_query = fields.join(",");
 
             build_query_param(&mut builder, &mut query_index, "select", select_query);
         }
 
-        let filters = self.filters();
-
-        if !filters.is_empty() {
-            builder += match query_index {
-                Some(_) => "&",
-                None => "?",
-            };
-            builder += &urlencoding::encode("q");
-            builder += "=";
-            query_index = None;
-
-            build_filter_query(query_index, &mut builder, &filters);
+        if let Some(query) = self.to_query() {
+            build_query_param(&mut builder, &mut query_index, "q", query);
         }
 
         builder
     }
 }
 
-fn build_filter_query(
-    mut query_index: Option<usize>,
-    builder: &mut String,
-    filters: &HashMap<String, String>,
-) {
-    for (filter_key, filter_value) in filters {
-        if query_index.is_some() {
-            *builder += " ";
-        }
-
-        let split = filter_value.split(',').collect::<Vec<&str>>();
-
-        if split.is_empty() {
-            *builder += &urlencoding::encode(filter_key);
-            *builder += ":";
-            *builder += &urlencoding::encode(&enclose_whitespace_strings(filter_value));
-        }
-
-        for sub_split in &split {
-            *builder += &urlencoding::encode(filter_key);
-            *builder += ":";
-            *builder += &urlencoding::encode(&enclose_whitespace_strings(sub_split));
-
-            if let Some(last) = split.last() {
-                if sub_split != last {
-                    *builder += &urlencoding::encode(" or ");
-                }
-            }
-        }
+/// Returns the sorted queries of the values of a filter, without duplicates.
+fn filter_queries(field: &str, values: &[FilterValue]) -> Vec<Query> {
+    let mut queries = values
+        .iter()
+        .map(|value| value.to_query(field))
+        .collect::<Vec<Query>>();
+    queries.sort_by_cached_key(ToString::to_string);
+    queries.dedup();
 
-        query_index = Some(0);
-    }
+    queries
 }
 
 fn build_query_param<T: Display>(
@@ -132,10 +317,112 @@ fn build_query_param<T: Display>(
     *query_index = Some(0);
 }
 
-fn enclose_whitespace_strings(value: &str) -> String {
-    if value.chars().any(char::is_whitespace) {
-        return format!("\"{value}\"");
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::{
+        field::{CardField, SetField},
+        pokemon::PokemonQueryBuilder,
+        set::SetQueryBuilder,
+    };
+
+    #[test]
+    fn equal_pokemon_builders_have_equal_keys() {
+        let first = PokemonQueryBuilder::new()
+            .add_name("charizard")
+            .add_types("fire")
+            .add_name(FilterValue::wildcard("char*"))
+            .exclude_rarity("common")
+            .exclude_rarity("rare")
+            .add_select(CardField::Name)
+            .add_select(CardField::Id)
+            .add_select(CardField::Name)
+            .add_name("charizard")
+            .with_page_size(10);
+        let second = PokemonQueryBuilder::new()
+            .with_page_size(10)
+            .add_select(CardField::Id)
+            .exclude_rarity("rare")
+            .add_name(FilterValue::wildcard("char*"))
+            .add_types("fire")
+            .add_select(CardField::Name)
+            .exclude_rarity("common")
+            .exclude_rarity("rare")
+            .add_name("charizard");
+
+        assert_eq!(first.canonical_key(), second.canonical_key());
+        assert_eq!(first.build("https://api"), second.build("https://api"));
+        assert_eq!(
+            first.canonical_key(),
+            "pageSize=10&select=id%2Cname&q=%28name%3Achar%2A%20OR%20name%3Acharizard%29%20types%3Afire%20-rarity%3Acommon%20-rarity%3Arare"
+        );
+    }
+
+    #[test]
+    fn equal_set_builders_have_equal_keys() {
+        let first = SetQueryBuilder::new()
+            .add_filter(
+                SetField::ReleaseDate,
+                FilterValue::range("2020/01/01", "*", true),
+            )
+            .add_name("base")
+            .add_name("jungle")
+            .add_select(SetField::ReleaseDate)
+            .add_select(SetField::Name);
+        let second = SetQueryBuilder::new()
+            .add_select(SetField::Name)
+            .add_name("jungle")
+            .add_select(SetField::ReleaseDate)
+            .add_name("base")
+            .add_name("jungle")
+            .add_filter(
+                SetField::ReleaseDate,
+                FilterValue::range("2020/01/01", "*", true),
+            );
+
+        assert_eq!(first.canonical_key(), second.canonical_key());
     }
 
-    value.to_string()
+    #[test]
+    fn normalizes_whitespace_of_values() {
+        assert_eq!(
+            PokemonQueryBuilder::new()
+                .add_name("mr.  mime")
+                .canonical_key(),
+            PokemonQueryBuilder::new()
+                .add_name(" mr. mime")
+                .canonical_key()
+        );
+    }
+
+    #[test]
+    fn different_builders_have_different_keys() {
+        let builder = PokemonQueryBuilder::new().add_name("charizard");
+
+        let different = [
+            builder.clone().add_name("charmander"),
+            builder.clone().exclude_name("charmander"),
+            builder.clone().add_name_exact("charizard"),
+            builder.clone().with_page(2),
+            builder
+                .clone()
+                .add_ordering(CardField::Name.ascending())
+                .add_ordering(CardField::Hp.ascending()),
+            builder
+                .clone()
+                .add_ordering(CardField::Hp.ascending())
+                .add_ordering(CardField::Name.ascending()),
+        ];
+
+        let mut keys = different
+            .iter()
+            .map(QueryBuilder::canonical_key)
+            .collect::<Vec<String>>();
+        keys.push(builder.canonical_key());
+        let count = keys.len();
+        keys.sort();
+        keys.dedup();
+
+        assert_eq!(keys.len(), count);
+    }
 }
@@ -0,0 +1,493 @@
+use std::{error::Error, fmt, ops::Range, str::FromStr};
+
+use super::query::Query;
+
+/// Error for query strings which cannot be parsed, pointing to the malformed part of the input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    /// Description of what is wrong with the input.
+    pub message: String,
+    /// Byte range of the malformed part of the input.
+    pub span: Range<usize>,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} at {}..{}",
+            self.message, self.span.start, self.span.end
+        )
+    }
+}
+
+impl Error for ParseError {}
+
+/// Parses a query string, see `parse_query`.
+impl FromStr for Query {
+    type Err = ParseError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        parse_query(input)
+    }
+}
+
+/// Parses a query string in the lucene like syntax of the api, e.g. `name:char* types:fire hp:[100 TO *]`.
+///
+/// Supported are terms (`field:value`), quoted phrases (`field:"two words"`), wildcards (`*`, `?`),
+/// ranges (`[a TO b]`, `{a TO b}`), exact matches (`!field:value`), negation (`-` or `NOT`),
+/// `AND`/`OR` (also `&&`/`||`, juxtaposition is an AND), parentheses and escaping with `\`.
+///
+/// # Arguments
+///
+/// * `input` - The query string to parse.
+///
+/// # Errors
+///
+/// Will return `Err` if the input is empty or malformed, with the span of the malformed part.
+pub fn parse_query(input: &str) -> Result<Query, ParseError> {
+    let mut parser = Parser { input, position: 0 };
+
+    parser.skip_whitespace();
+    if parser.is_at_end() {
+        return Err(parser.error("Expected a query", input.len()..input.len()));
+    }
+
+    let query = parser.parse_or()?;
+
+    parser.skip_whitespace();
+    if let Some(c) = parser.peek() {
+        let start = parser.position;
+        let message = if c == ')' {
+            "Unmatched closing parenthesis"
+        } else {
+            "Unexpected input"
+        };
+
+        return Err(parser.error(message, start..start + c.len_utf8()));
+    }
+
+    Ok(query)
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    position: usize,
+}
+
+impl Parser<'_> {
+    fn parse_or(&mut self) -> Result<Query, ParseError> {
+        let mut query = self.parse_and()?;
+
+        while self.consume_keyword(&["OR", "||"]) {
+            query = merge_or(query, self.parse_and()?);
+        }
+
+        Ok(query)
+    }
+
+    fn parse_and(&mut self) -> Result<Query, ParseError> {
+        let mut query = self.parse_unary()?;
+
+        loop {
+            self.skip_whitespace();
+
+            if self.is_at_end() || self.peek() == Some(')') || self.at_keyword(&["OR", "||"]) {
+                break;
+            }
+
+            self.consume_keyword(&["AND", "&&"]);
+            query = merge_and(query, self.parse_unary()?);
+        }
+
+        Ok(query)
+    }
+
+    fn parse_unary(&mut self) -> Result<Query, ParseError> {
+        self.skip_whitespace();
+
+        if self.consume_keyword(&["NOT"]) || self.consume_char('-') {
+            return Ok(self.parse_unary()?.not());
+        }
+
+        self.parse_primary()
+    }
+
+    fn parse_primary(&mut self) -> Result<Query, ParseError> {
+        self.skip_whitespace();
+        let start = self.position;
+
+        match self.peek() {
+            None => Err(self.error("Expected a term", start..start)),
+            Some('(') => {
+                self.position += 1;
+                let query = self.parse_or()?;
+
+                self.skip_whitespace();
+                if !self.consume_char(')') {
+                    return Err(self.error("Unclosed parenthesis", start..start + 1));
+                }
+
+                Ok(query)
+            }
+            Some(')') => Err(self.error("Expected a term", start..start + 1)),
+            Some(_) => self.parse_field_term(),
+        }
+    }
+
+    fn parse_field_term(&mut self) -> Result<Query, ParseError> {
+        let start = self.position;
+        let exact = self.consume_char('!');
+
+        let field_start = self.position;
+        while self
+            .peek()
+            .is_some_and(|c| c.is_alphanumeric() || matches!(c, '.' | '_'))
+        {
+            self.position += self.peek().map_or(0, char::len_utf8);
+        }
+        let field = &self.input[field_start..self.position];
+
+        if field.is_empty() {
+            let end = self.word_end();
+            return Err(self.error("Expected a field", start..end));
+        }
+        if !self.consume_char(':') {
+            let end = self.word_end();
+            return Err(self.error(&format!("Expected ':' after field '{field}'"), start..end));
+        }
+
+        let value_start = self.position;
+
+        match self.peek() {
+            Some('[' | '{') if !exact => self.parse_range(field),
+            Some('"') => {
+                let phrase = self.parse_phrase()?;
+
+                Ok(if exact {
+                    Query::exact(field, &phrase)
+                } else {
+                    Query::term(field, &phrase)
+                })
+            }
+            Some(c) if !c.is_whitespace() && c != ')' => {
+                let (word, is_pattern) = self.parse_word(&[])?;
+
+                Ok(match (exact, is_pattern) {
+                    (true, true) => {
+                        return Err(self.error(
+                            "Exact matches cannot contain wildcards",
+                            value_start..self.position,
+                        ))
+                    }
+                    (true, false) => Query::exact(field, &word),
+                    (false, true) => Query::wildcard(field, &word),
+                    (false, false) => Query::term(field, &word),
+                })
+            }
+            _ => Err(self.error(
+                &format!("Expected a value for field '{field}'"),
+                start..self.position,
+            )),
+        }
+    }
+
+    fn parse_range(&mut self, field: &str) -> Result<Query, ParseError> {
+        let start = self.position;
+        let inclusive = self.consume_char('[');
+        if !inclusive {
+            self.position += 1;
+        }
+
+        let low = self.parse_bound(start)?;
+
+        self.skip_whitespace();
+        if !self.consume_keyword(&["TO"]) {
+            let position = self.position;
+            return Err(self.error("Expected 'TO' in range", position..self.word_end()));
+        }
+
+        let high = self.parse_bound(start)?;
+
+        self.skip_whitespace();
+        let closing = if inclusive { ']' } else { '}' };
+        match self.peek() {
+            Some(c) if c == closing => {
+                self.position += 1;
+                Ok(Query::range(field, &low, &high, inclusive))
+            }
+            Some(c @ (']' | '}')) => {
+                let position = self.position;
+                Err(self.error(
+                    &format!(
+                        "Range opened with '{}' cannot be closed with '{c}'",
+                        if inclusive { '[' } else { '{' }
+                    ),
+                    position..position + 1,
+                ))
+            }
+            _ => Err(self.error("Unclosed range", start..self.position)),
+        }
+    }
+
+    fn parse_bound(&mut self, range_start: usize) -> Result<String, ParseError> {
+        self.skip_whitespace();
+
+        match self.peek() {
+            Some('"') => self.parse_phrase(),
+            Some(c) if !matches!(c, ']' | '}') => {
+                let start = self.position;
+                let (bound, is_pattern) = self.parse_word(&[']', '}'])?;
+
+                if is_pattern && bound != "*" {
+                    return Err(self.error(
+                        "Range bounds cannot contain wildcards",
+                        start..self.position,
+                    ));
+                }
+
+                Ok(bound)
+            }
+            _ => Err(self.error("Expected a range bound", range_start..self.position)),
+        }
+    }
+
+    /// Parses a quoted phrase, collapsing runs of whitespace into single spaces.
+    fn parse_phrase(&mut self) -> Result<String, ParseError> {
+        let start = self.position;
+        self.position += 1;
+
+        let mut phrase = String::new();
+
+        while let Some(c) = self.peek() {
+            self.position += c.len_utf8();
+
+            match c {
+                '"' => return Ok(phrase.split_whitespace().collect::<Vec<&str>>().join(" ")),
+                '\\' => match self.peek() {
+                    Some(escaped) => {
+                        self.position += escaped.len_utf8();
+                        phrase.push(escaped);
+                    }
+                    None => break,
+                },
+                c => phrase.push(c),
+            }
+        }
+
+        Err(self.error("Unclosed quote", start..self.input.len()))
+    }
+
+    /// Parses an unquoted value, resolving escapes.
+    /// Returns whether the value contains unescaped `*` or `?` placeholders.
+    fn parse_word(&mut self, terminators: &[char]) -> Result<(String, bool), ParseError> {
+        let mut word = String::new();
+        let mut is_pattern = false;
+
+        while let Some(c) = self.peek() {
+            if c.is_whitespace() || matches!(c, '(' | ')') || terminators.contains(&c) {
+                break;
+            }
+
+            let start = self.position;
+            self.position += c.len_utf8();
+
+            match c {
+                '\\' => match self.peek() {
+                    Some(escaped) => {
+                        self.position += escaped.len_utf8();
+                        word.push(escaped);
+                    }
+                    None => return Err(self.error("Dangling escape character", start..start + 1)),
+                },
+                '"' | '[' | ']' | '{' | '}' | ':' => {
+                    return Err(self.error(
+                        &format!("Unexpected '{c}', escape it with '\\'"),
+                        start..start + 1,
+                    ))
+                }
+                '*' | '?' => {
+                    is_pattern = true;
+                    word.push(c);
+                }
+                c => word.push(c),
+            }
+        }
+
+        Ok((word, is_pattern))
+    }
+
+    /// Consumes one of the keywords if it is the next word of the input.
+    fn consume_keyword(&mut self, keywords: &[&str]) -> bool {
+        self.skip_whitespace();
+
+        match keywords.iter().find(|keyword| self.is_keyword_at(keyword)) {
+            Some(keyword) => {
+                self.position += keyword.len();
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn at_keyword(&self, keywords: &[&str]) -> bool {
+        keywords.iter().any(|keyword| self.is_keyword_at(keyword))
+    }
+
+    fn is_keyword_at(&self, keyword: &str) -> bool {
+        let rest = &self.input[self.position..];
+
+        rest.starts_with(keyword)
+            && rest[keyword.len()..]
+                .chars()
+                .next()
+                .is_none_or(|c| c.is_whitespace() || matches!(c, '(' | '-' | '!' | '"' | '[' | '{'))
+    }
+
+    fn consume_char(&mut self, expected: char) -> bool {
+        if self.peek() == Some(expected) {
+            self.position += expected.len_utf8();
+            return true;
+        }
+
+        false
+    }
+
+    /// Returns the end of the word at the current position, used for the spans of errors.
+    fn word_end(&self) -> usize {
+        self.input[self.position..]
+            .find(|c: char| c.is_whitespace() || matches!(c, '(' | ')'))
+            .map_or(self.input.len(), |offset| self.position + offset)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.position += self.peek().map_or(0, char::len_utf8);
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.input[self.position..].chars().next()
+    }
+
+    fn is_at_end(&self) -> bool {
+        self.position >= self.input.len()
+    }
+
+    fn error(&self, message: &str, span: Range<usize>) -> ParseError {
+        ParseError {
+            message: String::from(message),
+            span,
+        }
+    }
+}
+
+/// Combines two queries with an OR, merging an OR from parentheses,
+/// so the query is the same as the one parsed from its rendered query string.
+fn merge_or(query: Query, other: Query) -> Query {
+    match other {
+        Query::Or(queries) => queries.into_iter().fold(query, Query::or),
+        other => query.or(other),
+    }
+}
+
+/// Combines two queries with an AND, merging an AND from parentheses,
+/// so the query is the same as the one parsed from its rendered query string.
+fn merge_and(query: Query, other: Query) -> Query {
+    match other {
+        Query::And(queries) => queries.into_iter().fold(query, Query::and),
+        other => query.and(other),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::{pokemon::PokemonQueryBuilder, QueryBuilder};
+
+    /// Parses the input, builds the url of a query builder with it and parses its `q` parameter again.
+    fn reparse_built(input: &str) -> Query {
+        let url = PokemonQueryBuilder::parse(input).unwrap().build("");
+        let q = url.strip_prefix("?q=").unwrap();
+
+        parse_query(&urlencoding::decode(q).unwrap()).unwrap()
+    }
+
+    fn parse_error(input: &str) -> (String, &str) {
+        let error = parse_query(input).unwrap_err();
+
+        (error.message, &input[error.span])
+    }
+
+    #[test]
+    fn parses_terms_ranges_and_wildcards() {
+        assert_eq!(
+            parse_query("name:char* types:fire hp:[100 TO *]").unwrap(),
+            Query::wildcard("name", "char*")
+                .and(Query::term("types", "fire"))
+                .and(Query::range("hp", "100", "*", true))
+        );
+        assert_eq!(
+            parse_query("!name:\"Charizard   ex\" OR -set.id:base1").unwrap(),
+            Query::exact("name", "Charizard ex").or(Query::term("set.id", "base1").not())
+        );
+        assert_eq!(
+            parse_query("name:a\\:b\\*").unwrap(),
+            Query::term("name", "a:b*")
+        );
+    }
+
+    #[test]
+    fn round_trips_through_build() {
+        let inputs = [
+            "name:char* types:fire hp:[100 TO *]",
+            "name:\"venusaur   v\" OR (types:water AND -subtypes:EX)",
+            "!name:charizard NOT set.id:base1 || hp:{10 TO 90}",
+            "name:jig\\ gly\\* -(types:fire types:water)",
+            "rarity:\"Rare \\\"Holo\\\"\" a:1 AND (b:2 OR c:3) OR d:4",
+        ];
+
+        for input in inputs {
+            assert_eq!(reparse_built(input), parse_query(input).unwrap(), "{input}");
+        }
+    }
+
+    #[test]
+    fn reports_error_spans() {
+        assert_eq!(
+            parse_error("name:\"abc"),
+            (String::from("Unclosed quote"), "\"abc")
+        );
+        assert_eq!(
+            parse_error("hp:[1 TO 2"),
+            (String::from("Unclosed range"), "[1 TO 2")
+        );
+        assert_eq!(
+            parse_error("hp:[1 TO 2}"),
+            (
+                String::from("Range opened with '[' cannot be closed with '}'"),
+                "}"
+            )
+        );
+        assert_eq!(
+            parse_error("name:abc\\"),
+            (String::from("Dangling escape character"), "\\")
+        );
+        assert_eq!(
+            parse_error("!name:char*"),
+            (
+                String::from("Exact matches cannot contain wildcards"),
+                "char*"
+            )
+        );
+    }
+
+    #[test]
+    fn parses_non_ascii_fields() {
+        assert_eq!(parse_query("é:x").unwrap(), Query::term("é", "x"));
+        assert_eq!(
+            parse_error("nameé x"),
+            (String::from("Expected ':' after field 'nameé'"), "nameé")
+        );
+    }
+}
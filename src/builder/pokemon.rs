@@ -1,67 +1,80 @@
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
-use crate::pokemon_api_client::api_client::CardId;
+use serde::{Deserialize, Serialize};
 
-use super::{Ordering, QueryBuilder};
+use crate::pokemon_api_client::api_client::{CardId, SetId};
+
+use super::{
+    field::CardField,
+    query::Query,
+    saved::{SavedSearch, SavedSearchError, SearchKind},
+    FilterValue, Ordering, QueryBuilder,
+};
 
 /// Query builder for pokemon card api requests.
-#[derive(Clone)]
+/// Serializes into and deserializes from a `SavedSearch`.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(into = "SavedSearch", try_from = "SavedSearch")]
 pub struct PokemonQueryBuilder {
-    filters: HashMap<String, String>,
+    filters: BTreeMap<String, Vec<FilterValue>>,
+    excluded_filters: BTreeMap<String, Vec<FilterValue>>,
     page: Option<u32>,
     page_size: Option<u8>,
     order_by: Vec<Ordering>,
     select_fields: Vec<String>,
+    query: Option<Query>,
 }
 
 impl PokemonQueryBuilder {
-    /// Sets the page size of the query builder.
+    /// Adds a parameter to the ordering list.
     ///
     /// # Arguments
     ///
-    /// * `size` - The size of the page.
+    /// * `ordering` - A fields to order by in the data.
     #[must_use]
-    pub fn with_page_size(self, size: u8) -> Self {
+    pub fn add_ordering(self, ordering: Ordering) -> Self {
         let mut new = self.clone();
-        new.page_size = Some(size);
+        new.order_by.push(ordering);
         new
     }
 
-    /// Sets the page from which it should get the results.
+    /// Adds a value to select from the requested data.
+    /// The returned data will only contain values with those fields.
     ///
     /// # Arguments
     ///
-    /// * `page` - The number of the page to select.
+    /// * `field` - A field to at to the query.
     #[must_use]
-    pub fn with_page(self, page: u32) -> Self {
+    pub fn add_select(self, field: impl Into<CardField>) -> Self {
         let mut new = self.clone();
-        new.page = Some(page);
+        new.select_fields.push(field.into().path());
         new
     }
 
-    /// Adds a parameter to the ordering list.
+    /// Adds a filter for any field to the query parameter, if used more than once for a field it turns into an OR.
     ///
     /// # Arguments
     ///
-    /// * `ordering` - A fields to order by in the data.
+    /// * `field` - The field to filter.
+    /// * `value` - The value of the field you want to filter for.
     #[must_use]
-    pub fn add_ordering(self, ordering: Ordering) -> Self {
-        let mut new = self.clone();
-        new.order_by.push(ordering);
-        new
+    pub fn add_filter(self, field: impl Into<CardField>, value: impl Into<FilterValue>) -> Self {
+        self.add_or_update_filter(&field.into().path(), value.into())
     }
 
-    /// Adds a value to select from the requested data.
-    /// The returned data will only contain values with those fields.
+    /// Excludes pokemon cards with the value in any field from the query.
     ///
     /// # Arguments
     ///
-    /// * `field` - A field to at to the query.
+    /// * `field` - The field to filter.
+    /// * `value` - The value of the field you want to exclude.
     #[must_use]
-    pub fn add_select(self, field: &str) -> Self {
-        let mut new = self.clone();
-        new.select_fields.push(String::from(field));
-        self
+    pub fn exclude_filter(
+        self,
+        field: impl Into<CardField>,
+        value: impl Into<FilterValue>,
+    ) -> Self {
+        self.add_exclusion(&field.into().path(), value.into())
     }
 
     /// Adds a id to the query parameter, if used more than once it turns into an OR.
@@ -71,7 +84,27 @@ impl PokemonQueryBuilder {
     /// * `id` - The id of the pokemon card you want to filter for.
     #[must_use]
     pub fn add_id(self, id: &CardId) -> Self {
-        self.add_or_update_filter("id", &id.0)
+        self.add_or_update_filter("id", FilterValue::Text(id.0.clone()))
+    }
+
+    /// Adds a card id which has to match exactly to the query parameter, if used more than once it turns into an OR.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The exact card id you want to filter for.
+    #[must_use]
+    pub fn add_id_exact(self, id: &CardId) -> Self {
+        self.add_or_update_filter("id", FilterValue::exact(&id.0))
+    }
+
+    /// Excludes results with the card id from the query, if used more than once results matching any of them are excluded.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The card id you want to exclude.
+    #[must_use]
+    pub fn exclude_id(self, id: &CardId) -> Self {
+        self.add_exclusion("id", FilterValue::Text(id.0.clone()))
     }
 
     /// Adds a card name to the query parameter, if used more than once it turns into an OR.
@@ -80,8 +113,28 @@ impl PokemonQueryBuilder {
     ///
     /// * `name` - The name of the pokemon card you want to filter for.
     #[must_use]
-    pub fn add_name(self, name: &str) -> Self {
-        self.add_or_update_filter("name", name)
+    pub fn add_name(self, name: impl Into<FilterValue>) -> Self {
+        self.add_or_update_filter("name", name.into())
+    }
+
+    /// Adds a card name which has to match exactly to the query parameter, if used more than once it turns into an OR.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The exact card name you want to filter for.
+    #[must_use]
+    pub fn add_name_exact(self, name: &str) -> Self {
+        self.add_or_update_filter("name", FilterValue::exact(name))
+    }
+
+    /// Excludes results with the card name from the query, if used more than once results matching any of them are excluded.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The card name you want to exclude.
+    #[must_use]
+    pub fn exclude_name(self, name: impl Into<FilterValue>) -> Self {
+        self.add_exclusion("name", name.into())
     }
 
     /// Adds a sub type to the query parameter, if used more than once it turns into an OR.
@@ -90,8 +143,28 @@ impl PokemonQueryBuilder {
     ///
     /// * `sub_types` - The sub type you want to query for.
     #[must_use]
-    pub fn add_sub_types(self, sub_type: &str) -> Self {
-        self.add_or_update_filter("subtypes", sub_type)
+    pub fn add_sub_types(self, sub_type: impl Into<FilterValue>) -> Self {
+        self.add_or_update_filter("subtypes", sub_type.into())
+    }
+
+    /// Adds a sub type which has to match exactly to the query parameter, if used more than once it turns into an OR.
+    ///
+    /// # Arguments
+    ///
+    /// * `sub_type` - The exact sub type you want to filter for.
+    #[must_use]
+    pub fn add_sub_types_exact(self, sub_type: &str) -> Self {
+        self.add_or_update_filter("subtypes", FilterValue::exact(sub_type))
+    }
+
+    /// Excludes results with the sub type from the query, if used more than once results matching any of them are excluded.
+    ///
+    /// # Arguments
+    ///
+    /// * `sub_type` - The sub type you want to exclude.
+    #[must_use]
+    pub fn exclude_sub_types(self, sub_type: impl Into<FilterValue>) -> Self {
+        self.add_exclusion("subtypes", sub_type.into())
     }
 
     /// Adds a hp range to the query parameter. Can be inclusive or exclusive.
@@ -103,11 +176,25 @@ impl PokemonQueryBuilder {
     /// * `is_inclusive` - Sets whether the range is inclusive.
     #[must_use]
     pub fn add_hp_range(self, low_value: &str, high_value: &str, is_inclusive: bool) -> Self {
-        if is_inclusive {
-            return self.add_or_update_filter("hp", &format!("[{low_value} TO {high_value}]"));
-        }
+        self.add_or_update_filter(
+            "hp",
+            FilterValue::range(low_value, high_value, is_inclusive),
+        )
+    }
 
-        self.add_or_update_filter("hp", &format!("{{{low_value} TO {high_value}}}"))
+    /// Excludes a hp range from the query. Can be inclusive or exclusive.
+    ///
+    /// # Arguments
+    ///
+    /// * `low_value` - The low value of the excluded range.
+    /// * `high_value` - The high value of the excluded range.
+    /// * `is_inclusive` - Sets whether the range is inclusive.
+    #[must_use]
+    pub fn exclude_hp_range(self, low_value: &str, high_value: &str, is_inclusive: bool) -> Self {
+        self.add_exclusion(
+            "hp",
+            FilterValue::range(low_value, high_value, is_inclusive),
+        )
     }
 
     /// Adds a type to the query parameter.
@@ -116,8 +203,28 @@ impl PokemonQueryBuilder {
     ///
     /// * `types` - The type of to add to the query.
     #[must_use]
-    pub fn add_types(self, types: &str) -> Self {
-        self.add_or_update_filter("types", types)
+    pub fn add_types(self, types: impl Into<FilterValue>) -> Self {
+        self.add_or_update_filter("types", types.into())
+    }
+
+    /// Adds a type which has to match exactly to the query parameter, if used more than once it turns into an OR.
+    ///
+    /// # Arguments
+    ///
+    /// * `types` - The exact type you want to filter for.
+    #[must_use]
+    pub fn add_types_exact(self, types: &str) -> Self {
+        self.add_or_update_filter("types", FilterValue::exact(types))
+    }
+
+    /// Excludes results with the type from the query, if used more than once results matching any of them are excluded.
+    ///
+    /// # Arguments
+    ///
+    /// * `types` - The type you want to exclude.
+    #[must_use]
+    pub fn exclude_types(self, types: impl Into<FilterValue>) -> Self {
+        self.add_exclusion("types", types.into())
     }
 
     /// Adds a pokemon name the searched pokemon evolves from to the query parameter.
@@ -126,8 +233,28 @@ impl PokemonQueryBuilder {
     ///
     /// * `evolves_from` - The evolved from pokemon to add to the query.
     #[must_use]
-    pub fn add_evolves_from(self, evolves_from: &str) -> Self {
-        self.add_or_update_filter("evolvesFrom", evolves_from)
+    pub fn add_evolves_from(self, evolves_from: impl Into<FilterValue>) -> Self {
+        self.add_or_update_filter("evolvesFrom", evolves_from.into())
+    }
+
+    /// Adds a pokemon name the card evolves from which has to match exactly to the query parameter, if used more than once it turns into an OR.
+    ///
+    /// # Arguments
+    ///
+    /// * `evolves_from` - The exact pokemon name the card evolves from you want to filter for.
+    #[must_use]
+    pub fn add_evolves_from_exact(self, evolves_from: &str) -> Self {
+        self.add_or_update_filter("evolvesFrom", FilterValue::exact(evolves_from))
+    }
+
+    /// Excludes results with the pokemon name the card evolves from from the query, if used more than once results matching any of them are excluded.
+    ///
+    /// # Arguments
+    ///
+    /// * `evolves_from` - The pokemon name the card evolves from you want to exclude.
+    #[must_use]
+    pub fn exclude_evolves_from(self, evolves_from: impl Into<FilterValue>) -> Self {
+        self.add_exclusion("evolvesFrom", evolves_from.into())
     }
 
     /// Adds a pokemon name the searched pokemon evolves tp to the query parameter.
@@ -136,8 +263,28 @@ impl PokemonQueryBuilder {
     ///
     /// * `evolves_to` - The evolved to pokemon to add to the query.
     #[must_use]
-    pub fn add_evolves_to(self, evolves_to: &str) -> Self {
-        self.add_or_update_filter("evolvesTo", evolves_to)
+    pub fn add_evolves_to(self, evolves_to: impl Into<FilterValue>) -> Self {
+        self.add_or_update_filter("evolvesTo", evolves_to.into())
+    }
+
+    /// Adds a pokemon name the card evolves to which has to match exactly to the query parameter, if used more than once it turns into an OR.
+    ///
+    /// # Arguments
+    ///
+    /// * `evolves_to` - The exact pokemon name the card evolves to you want to filter for.
+    #[must_use]
+    pub fn add_evolves_to_exact(self, evolves_to: &str) -> Self {
+        self.add_or_update_filter("evolvesTo", FilterValue::exact(evolves_to))
+    }
+
+    /// Excludes results with the pokemon name the card evolves to from the query, if used more than once results matching any of them are excluded.
+    ///
+    /// # Arguments
+    ///
+    /// * `evolves_to` - The pokemon name the card evolves to you want to exclude.
+    #[must_use]
+    pub fn exclude_evolves_to(self, evolves_to: impl Into<FilterValue>) -> Self {
+        self.add_exclusion("evolvesTo", evolves_to.into())
     }
 
     /// Adds a attack cost range to the query parameter. Can be inclusive or exclusive.
@@ -154,28 +301,91 @@ impl PokemonQueryBuilder {
         high_value: &str,
         is_inclusive: bool,
     ) -> Self {
-        if is_inclusive {
-            return self.add_or_update_filter(
-                "attacks.convertedEnergyCost",
-                &format!("[{low_value} TO {high_value}]"),
-            );
-        }
-
         self.add_or_update_filter(
             "attacks.convertedEnergyCost",
-            &format!("{{{low_value} TO {high_value}}}"),
+            FilterValue::range(low_value, high_value, is_inclusive),
+        )
+    }
+
+    /// Excludes a converted attack cost range from the query. Can be inclusive or exclusive.
+    ///
+    /// # Arguments
+    ///
+    /// * `low_value` - The low value of the excluded range.
+    /// * `high_value` - The high value of the excluded range.
+    /// * `is_inclusive` - Sets whether the range is inclusive.
+    #[must_use]
+    pub fn exclude_attack_cost_range(
+        self,
+        low_value: &str,
+        high_value: &str,
+        is_inclusive: bool,
+    ) -> Self {
+        self.add_exclusion(
+            "attacks.convertedEnergyCost",
+            FilterValue::range(low_value, high_value, is_inclusive),
         )
     }
 
-    /// Adds a set series to the query parameter, if used more than once it turns into an OR.
-    /// Look at Pokemon TCG Api wiki <https://docs.pokemontcg.io/api-reference/cards/search-cards/> for a documentation about wildcards.
+    /// Adds a set name to the query parameter, if used more than once it turns into an OR.
+    /// Look at Pokemon TCG Api wiki <https://docs.pokemontcg.io/api-reference/cards/search-cards/> for a documentation about wildcards, which are passed as `FilterValue::Wildcard`.
+    ///
+    /// # Arguments
+    ///
+    /// * `set_name` - The name of set of the card you want to query for.
+    #[must_use]
+    pub fn add_set_name(self, set_name: impl Into<FilterValue>) -> Self {
+        self.add_or_update_filter("set.name", set_name.into())
+    }
+
+    /// Adds a set name which has to match exactly to the query parameter, if used more than once it turns into an OR.
+    ///
+    /// # Arguments
+    ///
+    /// * `set_name` - The exact set name you want to filter for.
+    #[must_use]
+    pub fn add_set_name_exact(self, set_name: &str) -> Self {
+        self.add_or_update_filter("set.name", FilterValue::exact(set_name))
+    }
+
+    /// Excludes results with the set name from the query, if used more than once results matching any of them are excluded.
+    ///
+    /// # Arguments
+    ///
+    /// * `set_name` - The set name you want to exclude.
+    #[must_use]
+    pub fn exclude_set_name(self, set_name: impl Into<FilterValue>) -> Self {
+        self.add_exclusion("set.name", set_name.into())
+    }
+
+    /// Adds a set id to the query parameter, if used more than once it turns into an OR.
+    ///
+    /// # Arguments
+    ///
+    /// * `set_id` - The id of the set of the card you want to query for.
+    #[must_use]
+    pub fn add_set_id(self, set_id: &SetId) -> Self {
+        self.add_or_update_filter("set.id", FilterValue::Text(set_id.0.clone()))
+    }
+
+    /// Adds a set id which has to match exactly to the query parameter, if used more than once it turns into an OR.
+    ///
+    /// # Arguments
+    ///
+    /// * `set_id` - The exact set id you want to filter for.
+    #[must_use]
+    pub fn add_set_id_exact(self, set_id: &SetId) -> Self {
+        self.add_or_update_filter("set.id", FilterValue::exact(&set_id.0))
+    }
+
+    /// Excludes results with the set id from the query, if used more than once results matching any of them are excluded.
     ///
     /// # Arguments
     ///
-    /// * `set_series` - The name of set series of the card you want to query for.
+    /// * `set_id` - The set id you want to exclude.
     #[must_use]
-    pub fn add_set_name(self, set_name: &str) -> Self {
-        self.add_or_update_filter("set.series", set_name)
+    pub fn exclude_set_id(self, set_id: &SetId) -> Self {
+        self.add_exclusion("set.id", FilterValue::Text(set_id.0.clone()))
     }
 
     /// Adds a rarity to the query.
@@ -184,17 +394,43 @@ impl PokemonQueryBuilder {
     ///
     /// * `rarity` - The rarity of the pokemon card.
     #[must_use]
-    pub fn add_rarity(self, rarity: &str) -> Self {
-        self.add_or_update_filter("rarity", rarity)
+    pub fn add_rarity(self, rarity: impl Into<FilterValue>) -> Self {
+        self.add_or_update_filter("rarity", rarity.into())
     }
 
-    fn add_or_update_filter(mut self, key: &str, value: &str) -> Self {
-        if let Some(old_value) = self.filters.get_mut(key) {
-            *old_value = format!("{old_value},{value}");
-            return self;
-        }
+    /// Adds a rarity which has to match exactly to the query parameter, if used more than once it turns into an OR.
+    ///
+    /// # Arguments
+    ///
+    /// * `rarity` - The exact rarity you want to filter for.
+    #[must_use]
+    pub fn add_rarity_exact(self, rarity: &str) -> Self {
+        self.add_or_update_filter("rarity", FilterValue::exact(rarity))
+    }
+
+    /// Excludes results with the rarity from the query, if used more than once results matching any of them are excluded.
+    ///
+    /// # Arguments
+    ///
+    /// * `rarity` - The rarity you want to exclude.
+    #[must_use]
+    pub fn exclude_rarity(self, rarity: impl Into<FilterValue>) -> Self {
+        self.add_exclusion("rarity", rarity.into())
+    }
+
+    fn add_or_update_filter(mut self, key: &str, value: FilterValue) -> Self {
+        self.filters
+            .entry(String::from(key))
+            .or_default()
+            .push(value);
+        self
+    }
 
-        self.filters.insert(String::from(key), String::from(value));
+    fn add_exclusion(mut self, key: &str, value: FilterValue) -> Self {
+        self.excluded_filters
+            .entry(String::from(key))
+            .or_default()
+            .push(value);
         self
     }
 }
@@ -202,14 +438,26 @@ impl PokemonQueryBuilder {
 impl QueryBuilder for PokemonQueryBuilder {
     fn new() -> Self {
         PokemonQueryBuilder {
-            filters: HashMap::new(),
+            filters: BTreeMap::new(),
+            excluded_filters: BTreeMap::new(),
             page: None,
             page_size: None,
             order_by: Vec::new(),
             select_fields: Vec::new(),
+            query: None,
         }
     }
 
+    fn with_page_size(mut self, size: u8) -> Self {
+        self.page_size = Some(size);
+        self
+    }
+
+    fn with_page(mut self, page: u32) -> Self {
+        self.page = Some(page);
+        self
+    }
+
     fn page(&self) -> Option<u32> {
         self.page
     }
@@ -218,10 +466,14 @@ impl QueryBuilder for PokemonQueryBuilder {
         self.page_size
     }
 
-    fn filters(&self) -> HashMap<String, String> {
+    fn filters(&self) -> BTreeMap<String, Vec<FilterValue>> {
         self.filters.clone()
     }
 
+    fn excluded_filters(&self) -> BTreeMap<String, Vec<FilterValue>> {
+        self.excluded_filters.clone()
+    }
+
     fn order_by(&self) -> Vec<Ordering> {
         self.order_by.clone()
     }
@@ -229,4 +481,40 @@ impl QueryBuilder for PokemonQueryBuilder {
     fn select_fields(&self) -> Vec<String> {
         self.select_fields.clone()
     }
+
+    fn query(&self) -> Option<Query> {
+        self.query.clone()
+    }
+
+    fn with_query(mut self, query: Query) -> Self {
+        self.query = Some(match self.query {
+            Some(existing) => existing.and(query),
+            None => query,
+        });
+        self
+    }
+}
+
+impl From<PokemonQueryBuilder> for SavedSearch {
+    fn from(builder: PokemonQueryBuilder) -> Self {
+        SavedSearch::from_builder(SearchKind::Pokemon, &builder)
+    }
+}
+
+impl TryFrom<SavedSearch> for PokemonQueryBuilder {
+    type Error = SavedSearchError;
+
+    fn try_from(search: SavedSearch) -> Result<Self, Self::Error> {
+        let parts = search.into_parts(SearchKind::Pokemon)?;
+
+        Ok(PokemonQueryBuilder {
+            filters: parts.filters,
+            excluded_filters: parts.excluded_filters,
+            page: parts.page,
+            page_size: parts.page_size,
+            order_by: parts.order_by,
+            select_fields: parts.select_fields,
+            query: parts.query,
+        })
+    }
 }
@@ -0,0 +1,475 @@
+use std::fmt::{self, Display};
+
+/// Boolean expression in the lucene like syntax of the `q` parameter of the api.
+/// Field names are the json names of the api, nested fields are separated by dots, e.g. `set.series`.
+/// Build with the constructors and combinators, e.g. `Query::term("types", "Fire").or(Query::term("subtypes", "EX"))`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Query {
+    /// Matches fields containing the value, e.g. `name:charizard`.
+    Term { field: String, value: String },
+    /// Matches fields between the bounds, `*` is unbounded, e.g. `hp:[100 TO *]`.
+    /// Bounds are sent as they are, only bounds which would break the range are quoted.
+    Range {
+        field: String,
+        low: String,
+        high: String,
+        inclusive: bool,
+    },
+    /// Matches fields with `*` and `?` placeholders, e.g. `name:char*`.
+    /// All other special characters and whitespace are escaped.
+    Wildcard { field: String, pattern: String },
+    /// Matches fields which are exactly the value, e.g. `!name:charizard`.
+    Exact { field: String, value: String },
+    /// Value in the syntax of the api, which is sent without escaping.
+    Raw { field: String, value: String },
+    /// Matches if all of the queries match.
+    And(Vec<Query>),
+    /// Matches if any of the queries matches.
+    Or(Vec<Query>),
+    /// Matches if the query does not match.
+    Not(Box<Query>),
+    /// Explicitly groups a query in parentheses.
+    Group(Box<Query>),
+}
+
+impl Query {
+    /// Creates a query for fields containing the value.
+    ///
+    /// # Arguments
+    ///
+    /// * `field` - The field to filter.
+    /// * `value` - The value the field has to contain.
+    #[must_use]
+    pub fn term(field: &str, value: &str) -> Self {
+        Query::Term {
+            field: String::from(field),
+            value: String::from(value),
+        }
+    }
+
+    /// Creates a query for fields between two bounds, use `*` for an unbounded side.
+    ///
+    /// # Arguments
+    ///
+    /// * `field` - The field to filter.
+    /// * `low` - The low bound of the range.
+    /// * `high` - The high bound of the range.
+    /// * `inclusive` - Sets whether the bounds are part of the range.
+    #[must_use]
+    pub fn range(field: &str, low: &str, high: &str, inclusive: bool) -> Self {
+        Query::Range {
+            field: String::from(field),
+            low: String::from(low),
+            high: String::from(high),
+            inclusive,
+        }
+    }
+
+    /// Creates a query for fields matching a pattern with `*` and `?` placeholders.
+    ///
+    /// # Arguments
+    ///
+    /// * `field` - The field to filter.
+    /// * `pattern` - The pattern the field has to match.
+    #[must_use]
+    pub fn wildcard(field: &str, pattern: &str) -> Self {
+        Query::Wildcard {
+            field: String::from(field),
+            pattern: String::from(pattern),
+        }
+    }
+
+    /// Creates a query for fields which are exactly the value.
+    ///
+    /// # Arguments
+    ///
+    /// * `field` - The field to filter.
+    /// * `value` - The value the field has to be.
+    #[must_use]
+    pub fn exact(field: &str, value: &str) -> Self {
+        Query::Exact {
+            field: String::from(field),
+            value: String::from(value),
+        }
+    }
+
+    /// Creates a query with a value in the syntax of the api, which is sent without escaping.
+    ///
+    /// # Arguments
+    ///
+    /// * `field` - The field to filter.
+    /// * `value` - The value in the syntax of the api, e.g. `"venusaur v*"`.
+    #[must_use]
+    pub fn raw(field: &str, value: &str) -> Self {
+        Query::Raw {
+            field: String::from(field),
+            value: String::from(value),
+        }
+    }
+
+    /// Combines this and another query, both have to match.
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - The query which has to match as well.
+    #[must_use]
+    pub fn and(self, other: Query) -> Self {
+        match self {
+            Query::And(mut queries) => {
+                queries.push(other);
+                Query::And(queries)
+            }
+            query => Query::And(vec![query, other]),
+        }
+    }
+
+    /// Combines this and another query, either has to match.
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - The query which can match instead.
+    #[must_use]
+    pub fn or(self, other: Query) -> Self {
+        match self {
+            Query::Or(mut queries) => {
+                queries.push(other);
+                Query::Or(queries)
+            }
+            query => Query::Or(vec![query, other]),
+        }
+    }
+
+    /// Negates the query.
+    #[must_use]
+    #[allow(clippy::should_implement_trait)]
+    pub fn not(self) -> Self {
+        Query::Not(Box::new(self))
+    }
+
+    /// Groups the query in parentheses.
+    #[must_use]
+    pub fn group(self) -> Self {
+        Query::Group(Box::new(self))
+    }
+
+    /// Combines the queries so that all of them have to match, `None` if there are none.
+    ///
+    /// # Arguments
+    ///
+    /// * `queries` - The queries to combine.
+    #[must_use]
+    pub fn all(queries: Vec<Query>) -> Option<Self> {
+        combine(queries, Query::And)
+    }
+
+    /// Combines the queries so that any of them has to match, `None` if there are none.
+    ///
+    /// # Arguments
+    ///
+    /// * `queries` - The queries to combine.
+    #[must_use]
+    pub fn any(queries: Vec<Query>) -> Option<Self> {
+        combine(queries, Query::Or)
+    }
+
+    /// Interprets a raw value in the syntax of the api,
+    /// which can be a range in brackets, a pattern with placeholders or a plain value.
+    pub(crate) fn from_raw(field: &str, value: &str) -> Self {
+        let value = value.trim();
+
+        let inclusive = value.starts_with('[') && value.ends_with(']');
+        let exclusive = value.starts_with('{') && value.ends_with('}');
+
+        if inclusive || exclusive {
+            if let Some((low, high)) = value[1..value.len() - 1].split_once(" TO ") {
+                return Query::range(field, low.trim(), high.trim(), inclusive);
+            }
+        }
+
+        let value = normalize_whitespace(value.trim_matches('"'));
+
+        if value.contains(['*', '?']) {
+            return Query::wildcard(field, &value);
+        }
+
+        Query::term(field, &value)
+    }
+}
+
+fn combine(mut queries: Vec<Query>, combinator: fn(Vec<Query>) -> Query) -> Option<Query> {
+    match queries.len() {
+        0 => None,
+        1 => queries.pop(),
+        _ => Some(combinator(queries)),
+    }
+}
+
+/// Renders the query in the syntax of the `q` parameter.
+/// Nested `And`s in `Or`s and the other way around are put in parentheses to keep their meaning.
+impl Display for Query {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Query::Term { field, value } => write!(f, "{field}:{}", escape_text(value)),
+            Query::Range {
+                field,
+                low,
+                high,
+                inclusive: true,
+            } => write!(
+                f,
+                "{field}:[{} TO {}]",
+                escape_bound(low),
+                escape_bound(high)
+            ),
+            Query::Range {
+                field,
+                low,
+                high,
+                inclusive: false,
+            } => write!(
+                f,
+                "{field}:{{{} TO {}}}",
+                escape_bound(low),
+                escape_bound(high)
+            ),
+            Query::Wildcard { field, pattern } => {
+                write!(f, "{field}:{}", escape_wildcard(pattern))
+            }
+            Query::Exact { field, value } => write!(f, "!{field}:{}", escape_text(value)),
+            Query::Raw { field, value } => write!(f, "{field}:{value}"),
+            Query::And(queries) => write_joined(
+                f,
+                queries,
+                " ",
+                |query| matches!(query, Query::Or(queries) if queries.len() > 1),
+            ),
+            Query::Or(queries) => write_joined(
+                f,
+                queries,
+                " OR ",
+                |query| matches!(query, Query::And(queries) if queries.len() > 1),
+            ),
+            Query::Not(query) => match query.as_ref() {
+                Query::And(_) | Query::Or(_) => write!(f, "-({query})"),
+                query => write!(f, "-{query}"),
+            },
+            Query::Group(query) => write!(f, "({query})"),
+        }
+    }
+}
+
+fn write_joined(
+    f: &mut fmt::Formatter<'_>,
+    queries: &[Query],
+    separator: &str,
+    needs_parentheses: fn(&Query) -> bool,
+) -> fmt::Result {
+    for (index, query) in queries.iter().enumerate() {
+        if index > 0 {
+            f.write_str(separator)?;
+        }
+
+        if needs_parentheses(query) {
+            write!(f, "({query})")?;
+        } else {
+            write!(f, "{query}")?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Characters with a meaning in the query syntax, which have to be escaped in values.
+static SPECIAL_CHARACTERS: &[char] = &[
+    '+', '-', '&', '|', '!', '(', ')', '{', '}', '[', ']', '^', '"', '~', '*', '?', ':', '\\', '/',
+];
+
+/// Escapes a text value, values containing whitespace are quoted instead.
+/// Runs of whitespace are collapsed into single spaces.
+fn escape_text(value: &str) -> String {
+    let value = normalize_whitespace(value);
+
+    if value.contains(' ') {
+        return quote(&value);
+    }
+
+    escape(&value, |_| false)
+}
+
+/// Escapes every special character of a pattern except the `*` and `?` placeholders.
+/// Whitespace is escaped as well, as quoted values do not support placeholders.
+fn escape_wildcard(pattern: &str) -> String {
+    escape(&normalize_whitespace(pattern), |c| matches!(c, '*' | '?')).replace(' ', "\\ ")
+}
+
+/// Keeps a bound of a range as it is, e.g. `2020/01/01` or `-10`, and `*` for unbounded sides.
+/// Bounds which would end the range or be read as placeholders are quoted instead.
+fn escape_bound(bound: &str) -> String {
+    let bound = normalize_whitespace(bound);
+
+    let needs_quotes = bound.is_empty()
+        || bound == "TO"
+        || bound.contains(|c: char| {
+            matches!(
+                c,
+                ' ' | '"' | '\\' | '[' | ']' | '{' | '}' | '(' | ')' | ':' | '*' | '?'
+            )
+        });
+
+    if needs_quotes && bound != "*" {
+        quote(&bound)
+    } else {
+        bound
+    }
+}
+
+fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn escape(value: &str, is_kept: impl Fn(char) -> bool) -> String {
+    let mut escaped = String::with_capacity(value.len());
+
+    for c in value.chars() {
+        if SPECIAL_CHARACTERS.contains(&c) && !is_kept(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+
+    escaped
+}
+
+fn normalize_whitespace(value: &str) -> String {
+    value.split_whitespace().collect::<Vec<&str>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::parser::parse_query;
+
+    #[test]
+    fn displays_terms_and_exact_matches() {
+        assert_eq!(
+            Query::term("name", "charizard").to_string(),
+            "name:charizard"
+        );
+        assert_eq!(
+            Query::term("name", "Porygon-Z").to_string(),
+            "name:Porygon\\-Z"
+        );
+        assert_eq!(
+            Query::term("number", "a:b/c(1)").to_string(),
+            "number:a\\:b\\/c\\(1\\)"
+        );
+        assert_eq!(
+            Query::term("name", "  mr.\t mime ").to_string(),
+            "name:\"mr. mime\""
+        );
+        assert_eq!(
+            Query::term("flavorText", "say \"hi\" \\o/").to_string(),
+            "flavorText:\"say \\\"hi\\\" \\\\o/\""
+        );
+        assert_eq!(
+            Query::exact("name", "charizard").to_string(),
+            "!name:charizard"
+        );
+        assert_eq!(
+            Query::exact("name", "Mr. Mime").to_string(),
+            "!name:\"Mr. Mime\""
+        );
+        assert_eq!(
+            Query::raw("name", "\"venusaur v*\"").to_string(),
+            "name:\"venusaur v*\""
+        );
+    }
+
+    #[test]
+    fn displays_ranges_with_unescaped_bounds() {
+        assert_eq!(
+            Query::range("hp", "100", "*", true).to_string(),
+            "hp:[100 TO *]"
+        );
+        assert_eq!(
+            Query::range("hp", "*", "*", false).to_string(),
+            "hp:{* TO *}"
+        );
+        assert_eq!(
+            Query::range("set.releaseDate", "2020/01/01", "2021/12/31", true).to_string(),
+            "set.releaseDate:[2020/01/01 TO 2021/12/31]"
+        );
+        assert_eq!(
+            Query::range("weaknesses.value", "-10", "+30", false).to_string(),
+            "weaknesses.value:{-10 TO +30}"
+        );
+        assert_eq!(
+            Query::range("set.updatedAt", "2020/01/01  10:00:00", " * ", true).to_string(),
+            "set.updatedAt:[\"2020/01/01 10:00:00\" TO *]"
+        );
+        assert_eq!(
+            Query::range("name", "a]", "TO", true).to_string(),
+            "name:[\"a]\" TO \"TO\"]"
+        );
+    }
+
+    #[test]
+    fn displays_wildcards() {
+        assert_eq!(Query::wildcard("name", "char*").to_string(), "name:char*");
+        assert_eq!(Query::wildcard("name", "ho-o?").to_string(), "name:ho\\-o?");
+        assert_eq!(
+            Query::wildcard("name", "mr.  mi*").to_string(),
+            "name:mr.\\ mi*"
+        );
+    }
+
+    #[test]
+    fn displays_nested_combinations() {
+        let (a, b, c) = (
+            Query::term("a", "1"),
+            Query::term("b", "2"),
+            Query::term("c", "3"),
+        );
+
+        assert_eq!(
+            a.clone().and(b.clone()).and(c.clone()).to_string(),
+            "a:1 b:2 c:3"
+        );
+        assert_eq!(
+            a.clone().or(b.clone()).and(c.clone()).to_string(),
+            "(a:1 OR b:2) c:3"
+        );
+        assert_eq!(
+            a.clone().and(b.clone()).or(c.clone()).to_string(),
+            "(a:1 b:2) OR c:3"
+        );
+        assert_eq!(
+            Query::Or(vec![a.clone().and(b.clone()), c.clone().or(a.clone())]).to_string(),
+            "(a:1 b:2) OR c:3 OR a:1"
+        );
+        assert_eq!(a.clone().not().to_string(), "-a:1");
+        assert_eq!(a.clone().or(b.clone()).not().to_string(), "-(a:1 OR b:2)");
+        assert_eq!(
+            a.clone().and(b.clone()).not().and(c).to_string(),
+            "-(a:1 b:2) c:3"
+        );
+        assert_eq!(a.or(b).group().to_string(), "(a:1 OR b:2)");
+    }
+
+    #[test]
+    fn displayed_values_parse_back() {
+        let queries = [
+            Query::term("name", "Porygon-Z"),
+            Query::term("name", "mr. mime"),
+            Query::exact("name", "a:b/c"),
+            Query::range("set.releaseDate", "2020/01/01", "*", true),
+            Query::range("weaknesses.value", "-10", "+30", false),
+            Query::range("set.updatedAt", "2020/01/01 10:00:00", "*", true),
+            Query::wildcard("name", "ho-o?"),
+        ];
+
+        for query in queries {
+            assert_eq!(parse_query(&query.to_string()).unwrap(), query, "{query}");
+        }
+    }
+}
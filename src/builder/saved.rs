@@ -0,0 +1,567 @@
+use std::{collections::BTreeMap, error::Error, fmt};
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+    energy::EnergyQueryBuilder, pokemon::PokemonQueryBuilder, query::Query, set::SetQueryBuilder,
+    trainer::TrainerQueryBuilder, FilterValue, Ordering, QueryBuilder,
+};
+
+/// Version of the saved search format written by this crate.
+/// Saved searches of older versions can still be loaded, newer versions are rejected.
+pub const SAVED_SEARCH_VERSION: u32 = 1;
+
+/// The query builder a saved search belongs to.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchKind {
+    Pokemon,
+    Trainer,
+    Energy,
+    Set,
+}
+
+impl fmt::Display for SearchKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            SearchKind::Pokemon => "pokemon",
+            SearchKind::Trainer => "trainer",
+            SearchKind::Energy => "energy",
+            SearchKind::Set => "set",
+        };
+
+        f.write_str(name)
+    }
+}
+
+/// Error for saved searches which cannot be loaded into a query builder.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SavedSearchError {
+    /// The saved search was written by a newer version of this crate.
+    UnsupportedVersion(u32),
+    /// The saved search belongs to another query builder.
+    WrongKind {
+        expected: SearchKind,
+        found: SearchKind,
+    },
+}
+
+impl fmt::Display for SavedSearchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SavedSearchError::UnsupportedVersion(version) => write!(
+                f,
+                "Saved search version {version} is not supported, the latest supported version is {SAVED_SEARCH_VERSION}"
+            ),
+            SavedSearchError::WrongKind { expected, found } => write!(
+                f,
+                "Expected a saved {expected} search, found a saved {found} search"
+            ),
+        }
+    }
+}
+
+impl Error for SavedSearchError {}
+
+/// Stable, versioned representation of a query builder for saving and sharing searches, e.g. as json or toml.
+/// The query builders serialize into and deserialize from this format,
+/// `SearchBuilder` loads a saved search of any kind into the matching query builder.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct SavedSearch {
+    /// Version of the format, see `SAVED_SEARCH_VERSION`.
+    pub version: u32,
+    pub kind: SearchKind,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub page: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub page_size: Option<u8>,
+    /// Orderings in the syntax of `orderBy`, descending fields are prefixed with `-`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub order_by: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub select: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub query: Option<SavedQuery>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub filters: BTreeMap<String, Vec<SavedFilterValue>>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub excluded_filters: BTreeMap<String, Vec<SavedFilterValue>>,
+}
+
+/// Saved form of a `FilterValue`, tagged with its `type`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum SavedFilterValue {
+    Text {
+        value: String,
+    },
+    Wildcard {
+        pattern: String,
+    },
+    Exact {
+        value: String,
+    },
+    Range {
+        low: String,
+        high: String,
+        inclusive: bool,
+    },
+    Raw {
+        value: String,
+    },
+}
+
+/// Saved form of a `Query`, tagged with its `type`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum SavedQuery {
+    Term {
+        field: String,
+        value: String,
+    },
+    Range {
+        field: String,
+        low: String,
+        high: String,
+        inclusive: bool,
+    },
+    Wildcard {
+        field: String,
+        pattern: String,
+    },
+    Exact {
+        field: String,
+        value: String,
+    },
+    Raw {
+        field: String,
+        value: String,
+    },
+    And {
+        queries: Vec<SavedQuery>,
+    },
+    Or {
+        queries: Vec<SavedQuery>,
+    },
+    Not {
+        query: Box<SavedQuery>,
+    },
+    Group {
+        query: Box<SavedQuery>,
+    },
+}
+
+/// Contents of a saved search in the form the query builders store them.
+pub(crate) struct SearchParts {
+    pub(crate) filters: BTreeMap<String, Vec<FilterValue>>,
+    pub(crate) excluded_filters: BTreeMap<String, Vec<FilterValue>>,
+    pub(crate) page: Option<u32>,
+    pub(crate) page_size: Option<u8>,
+    pub(crate) order_by: Vec<Ordering>,
+    pub(crate) select_fields: Vec<String>,
+    pub(crate) query: Option<Query>,
+}
+
+impl SavedSearch {
+    /// Creates a saved search of the latest version from the state of a query builder.
+    pub(crate) fn from_builder<Q: QueryBuilder>(kind: SearchKind, query_builder: &Q) -> Self {
+        SavedSearch {
+            version: SAVED_SEARCH_VERSION,
+            kind,
+            page: query_builder.page(),
+            page_size: query_builder.page_size(),
+            order_by: query_builder
+                .order_by()
+                .iter()
+                .map(ToString::to_string)
+                .collect(),
+            select: query_builder.select_fields(),
+            query: query_builder.query().map(SavedQuery::from),
+            filters: save_filters(query_builder.filters()),
+            excluded_filters: save_filters(query_builder.excluded_filters()),
+        }
+    }
+
+    /// Converts the saved search into the state of a query builder of the expected kind.
+    pub(crate) fn into_parts(self, expected: SearchKind) -> Result<SearchParts, SavedSearchError> {
+        if self.version > SAVED_SEARCH_VERSION {
+            return Err(SavedSearchError::UnsupportedVersion(self.version));
+        }
+        if self.kind != expected {
+            return Err(SavedSearchError::WrongKind {
+                expected,
+                found: self.kind,
+            });
+        }
+
+        Ok(SearchParts {
+            filters: load_filters(self.filters),
+            excluded_filters: load_filters(self.excluded_filters),
+            page: self.page,
+            page_size: self.page_size,
+            order_by: self
+                .order_by
+                .iter()
+                .map(|ordering| match ordering.strip_prefix('-') {
+                    Some(field) => Ordering::Descending(String::from(field)),
+                    None => Ordering::Ascending(ordering.clone()),
+                })
+                .collect(),
+            select_fields: self.select,
+            query: self.query.map(Query::from),
+        })
+    }
+
+    /// Loads the saved search into the query builder of its kind.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the saved search was written by a newer version of this crate.
+    pub fn load(self) -> Result<SearchBuilder, SavedSearchError> {
+        SearchBuilder::try_from(self)
+    }
+}
+
+/// Query builder of any kind, as loaded from a saved search.
+/// Deserializing it loads a saved search into the query builder of the kind stored in it.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(into = "SavedSearch", try_from = "SavedSearch")]
+pub enum SearchBuilder {
+    Pokemon(PokemonQueryBuilder),
+    Trainer(TrainerQueryBuilder),
+    Energy(EnergyQueryBuilder),
+    Set(SetQueryBuilder),
+}
+
+impl SearchBuilder {
+    /// Returns the kind of the query builder.
+    #[must_use]
+    pub fn kind(&self) -> SearchKind {
+        match self {
+            SearchBuilder::Pokemon(_) => SearchKind::Pokemon,
+            SearchBuilder::Trainer(_) => SearchKind::Trainer,
+            SearchBuilder::Energy(_) => SearchKind::Energy,
+            SearchBuilder::Set(_) => SearchKind::Set,
+        }
+    }
+}
+
+impl TryFrom<SavedSearch> for SearchBuilder {
+    type Error = SavedSearchError;
+
+    fn try_from(search: SavedSearch) -> Result<Self, Self::Error> {
+        Ok(match search.kind {
+            SearchKind::Pokemon => SearchBuilder::Pokemon(PokemonQueryBuilder::try_from(search)?),
+            SearchKind::Trainer => SearchBuilder::Trainer(TrainerQueryBuilder::try_from(search)?),
+            SearchKind::Energy => SearchBuilder::Energy(EnergyQueryBuilder::try_from(search)?),
+            SearchKind::Set => SearchBuilder::Set(SetQueryBuilder::try_from(search)?),
+        })
+    }
+}
+
+impl From<SearchBuilder> for SavedSearch {
+    fn from(builder: SearchBuilder) -> Self {
+        match builder {
+            SearchBuilder::Pokemon(builder) => SavedSearch::from(builder),
+            SearchBuilder::Trainer(builder) => SavedSearch::from(builder),
+            SearchBuilder::Energy(builder) => SavedSearch::from(builder),
+            SearchBuilder::Set(builder) => SavedSearch::from(builder),
+        }
+    }
+}
+
+impl From<FilterValue> for SavedFilterValue {
+    fn from(value: FilterValue) -> Self {
+        match value {
+            FilterValue::Text(value) => SavedFilterValue::Text { value },
+            FilterValue::Wildcard(pattern) => SavedFilterValue::Wildcard { pattern },
+            FilterValue::Exact(value) => SavedFilterValue::Exact { value },
+            FilterValue::Range {
+                low,
+                high,
+                inclusive,
+            } => SavedFilterValue::Range {
+                low,
+                high,
+                inclusive,
+            },
+            FilterValue::Raw(value) => SavedFilterValue::Raw { value },
+        }
+    }
+}
+
+impl From<SavedFilterValue> for FilterValue {
+    fn from(value: SavedFilterValue) -> Self {
+        match value {
+            SavedFilterValue::Text { value } => FilterValue::Text(value),
+            SavedFilterValue::Wildcard { pattern } => FilterValue::Wildcard(pattern),
+            SavedFilterValue::Exact { value } => FilterValue::Exact(value),
+            SavedFilterValue::Range {
+                low,
+                high,
+                inclusive,
+            } => FilterValue::Range {
+                low,
+                high,
+                inclusive,
+            },
+            SavedFilterValue::Raw { value } => FilterValue::Raw(value),
+        }
+    }
+}
+
+impl From<Query> for SavedQuery {
+    fn from(query: Query) -> Self {
+        match query {
+            Query::Term { field, value } => SavedQuery::Term { field, value },
+            Query::Range {
+                field,
+                low,
+                high,
+                inclusive,
+            } => SavedQuery::Range {
+                field,
+                low,
+                high,
+                inclusive,
+            },
+            Query::Wildcard { field, pattern } => SavedQuery::Wildcard { field, pattern },
+            Query::Exact { field, value } => SavedQuery::Exact { field, value },
+            Query::Raw { field, value } => SavedQuery::Raw { field, value },
+            Query::And(queries) => SavedQuery::And {
+                queries: queries.into_iter().map(SavedQuery::from).collect(),
+            },
+            Query::Or(queries) => SavedQuery::Or {
+                queries: queries.into_iter().map(SavedQuery::from).collect(),
+            },
+            Query::Not(query) => SavedQuery::Not {
+                query: Box::new(SavedQuery::from(*query)),
+            },
+            Query::Group(query) => SavedQuery::Group {
+                query: Box::new(SavedQuery::from(*query)),
+            },
+        }
+    }
+}
+
+impl From<SavedQuery> for Query {
+    fn from(query: SavedQuery) -> Self {
+        match query {
+            SavedQuery::Term { field, value } => Query::Term { field, value },
+            SavedQuery::Range {
+                field,
+                low,
+                high,
+                inclusive,
+            } => Query::Range {
+                field,
+                low,
+                high,
+                inclusive,
+            },
+            SavedQuery::Wildcard { field, pattern } => Query::Wildcard { field, pattern },
+            SavedQuery::Exact { field, value } => Query::Exact { field, value },
+            SavedQuery::Raw { field, value } => Query::Raw { field, value },
+            SavedQuery::And { queries } => {
+                Query::And(queries.into_iter().map(Query::from).collect())
+            }
+            SavedQuery::Or { queries } => Query::Or(queries.into_iter().map(Query::from).collect()),
+            SavedQuery::Not { query } => Query::Not(Box::new(Query::from(*query))),
+            SavedQuery::Group { query } => Query::Group(Box::new(Query::from(*query))),
+        }
+    }
+}
+
+fn save_filters(
+    filters: BTreeMap<String, Vec<FilterValue>>,
+) -> BTreeMap<String, Vec<SavedFilterValue>> {
+    filters
+        .into_iter()
+        .map(|(field, values)| {
+            (
+                field,
+                values.into_iter().map(SavedFilterValue::from).collect(),
+            )
+        })
+        .collect()
+}
+
+fn load_filters(
+    filters: BTreeMap<String, Vec<SavedFilterValue>>,
+) -> BTreeMap<String, Vec<FilterValue>> {
+    filters
+        .into_iter()
+        .map(|(field, values)| (field, values.into_iter().map(FilterValue::from).collect()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        builder::field::{CardField, SetField},
+        pokemon_api_client::api_client::SetId,
+    };
+
+    /// A saved search of version 1, which has to stay loadable.
+    static VERSION_1_FIXTURE: &str = r#"{
+        "version": 1,
+        "kind": "pokemon",
+        "page": 2,
+        "pageSize": 50,
+        "orderBy": ["name", "-set.releaseDate"],
+        "select": ["id", "name"],
+        "query": {
+            "type": "not",
+            "query": {
+                "type": "or",
+                "queries": [
+                    { "type": "term", "field": "set.series", "value": "base" },
+                    { "type": "wildcard", "field": "subtypes", "pattern": "ex*" }
+                ]
+            }
+        },
+        "filters": {
+            "hp": [{ "type": "range", "low": "100", "high": "*", "inclusive": true }],
+            "name": [
+                { "type": "text", "value": "charizard" },
+                { "type": "wildcard", "pattern": "char*" }
+            ],
+            "nationalPokedexNumbers": [{ "type": "raw", "value": "[1 TO 151]" }],
+            "types": [{ "type": "exact", "value": "Fire" }]
+        },
+        "excludedFilters": {
+            "rarity": [{ "type": "text", "value": "common" }]
+        }
+    }"#;
+
+    fn pokemon_builder() -> PokemonQueryBuilder {
+        PokemonQueryBuilder::new()
+            .add_name("charizard")
+            .add_name(FilterValue::wildcard("char*"))
+            .add_hp_range("100", "*", true)
+            .add_types_exact("Fire")
+            .add_filter(
+                CardField::Raw(String::from("nationalPokedexNumbers")),
+                FilterValue::raw("[1 TO 151]"),
+            )
+            .exclude_rarity("common")
+            .add_ordering(CardField::Name.ascending())
+            .add_ordering(CardField::SetField(SetField::ReleaseDate).descending())
+            .add_select(CardField::Id)
+            .add_select(CardField::Name)
+            .with_query(
+                Query::term("set.series", "base")
+                    .or(Query::wildcard("subtypes", "ex*"))
+                    .not(),
+            )
+            .with_page(2)
+            .with_page_size(50)
+    }
+
+    fn canonical_key(builder: &SearchBuilder) -> String {
+        match builder {
+            SearchBuilder::Pokemon(builder) => builder.canonical_key(),
+            SearchBuilder::Trainer(builder) => builder.canonical_key(),
+            SearchBuilder::Energy(builder) => builder.canonical_key(),
+            SearchBuilder::Set(builder) => builder.canonical_key(),
+        }
+    }
+
+    #[test]
+    fn writes_version_1_format() {
+        let fixture: serde_json::Value = serde_json::from_str(VERSION_1_FIXTURE).unwrap();
+
+        assert_eq!(serde_json::to_value(pokemon_builder()).unwrap(), fixture);
+    }
+
+    #[test]
+    fn reads_version_1_format() {
+        let builder: PokemonQueryBuilder = serde_json::from_str(VERSION_1_FIXTURE).unwrap();
+
+        assert_eq!(builder.canonical_key(), pokemon_builder().canonical_key());
+        assert_eq!(builder.order_by(), pokemon_builder().order_by());
+    }
+
+    #[test]
+    fn round_trips_every_kind() {
+        let builders = [
+            SearchBuilder::Pokemon(pokemon_builder()),
+            SearchBuilder::Trainer(
+                TrainerQueryBuilder::new()
+                    .add_name_exact("Professor's Research")
+                    .exclude_set_series("base")
+                    .with_page_size(10),
+            ),
+            SearchBuilder::Energy(
+                EnergyQueryBuilder::new()
+                    .add_sub_types("special")
+                    .add_set_series_exact("Sword & Shield")
+                    .add_ordering(CardField::Name.descending()),
+            ),
+            SearchBuilder::Set(
+                SetQueryBuilder::new()
+                    .add_id(&SetId(String::from("base1")))
+                    .add_filter(
+                        SetField::ReleaseDate,
+                        FilterValue::range("2020/01/01", "*", true),
+                    )
+                    .add_select(SetField::Name)
+                    .with_page(3),
+            ),
+        ];
+
+        for builder in builders {
+            let json = serde_json::to_string(&builder).unwrap();
+            let loaded: SearchBuilder = serde_json::from_str(&json).unwrap();
+
+            assert_eq!(loaded.kind(), builder.kind());
+            assert_eq!(canonical_key(&loaded), canonical_key(&builder), "{json}");
+        }
+    }
+
+    #[test]
+    fn load_rebuilds_equivalent_builder() {
+        let loaded = SavedSearch::from(pokemon_builder()).load().unwrap();
+
+        assert_eq!(loaded.kind(), SearchKind::Pokemon);
+        assert_eq!(canonical_key(&loaded), pokemon_builder().canonical_key());
+    }
+
+    #[test]
+    fn rejects_newer_versions() {
+        let mut search = SavedSearch::from(pokemon_builder());
+        search.version = SAVED_SEARCH_VERSION + 1;
+
+        assert!(matches!(
+            search.clone().load(),
+            Err(SavedSearchError::UnsupportedVersion(version)) if version == SAVED_SEARCH_VERSION + 1
+        ));
+        assert!(serde_json::from_value::<PokemonQueryBuilder>(
+            serde_json::to_value(search).unwrap()
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn rejects_other_kinds() {
+        let search = SavedSearch::from(TrainerQueryBuilder::new().add_name("research"));
+
+        assert!(matches!(
+            PokemonQueryBuilder::try_from(search),
+            Err(SavedSearchError::WrongKind {
+                expected: SearchKind::Pokemon,
+                found: SearchKind::Trainer,
+            })
+        ));
+    }
+
+    #[test]
+    fn round_trips_through_toml() {
+        let toml = toml::to_string(&pokemon_builder()).unwrap();
+        let builder: PokemonQueryBuilder = toml::from_str(&toml).unwrap();
+
+        assert_eq!(builder.canonical_key(), pokemon_builder().canonical_key());
+    }
+}
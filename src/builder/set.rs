@@ -1,67 +1,76 @@
-use std::collections::HashMap;
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
 
 use crate::pokemon_api_client::api_client::SetId;
 
-use super::{Ordering, QueryBuilder};
+use super::{
+    field::SetField,
+    query::Query,
+    saved::{SavedSearch, SavedSearchError, SearchKind},
+    FilterValue, Ordering, QueryBuilder,
+};
 
 /// Query builder for creating set api query urls.
-#[derive(Clone)]
+/// Serializes into and deserializes from a `SavedSearch`.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(into = "SavedSearch", try_from = "SavedSearch")]
 pub struct SetQueryBuilder {
-    filters: HashMap<String, String>,
+    filters: BTreeMap<String, Vec<FilterValue>>,
+    excluded_filters: BTreeMap<String, Vec<FilterValue>>,
     page: Option<u32>,
     page_size: Option<u8>,
     order_by: Vec<Ordering>,
     select_fields: Vec<String>,
+    query: Option<Query>,
 }
 
 impl SetQueryBuilder {
-    /// Sets the page size of the query builder.
+    /// Adds a parameter to the ordering list.
     ///
     /// # Arguments
     ///
-    /// * `size` - The size of the page.
+    /// * `ordering` - A fields to order by in the data.
     #[must_use]
-    pub fn with_page_size(self, size: u8) -> Self {
+    pub fn add_ordering(self, ordering: Ordering) -> Self {
         let mut new = self.clone();
-        new.page_size = Some(size);
+        new.order_by.push(ordering);
         new
     }
 
-    /// Sets the page from which it should get the results.
+    /// Adds a value to select from the requested data.
+    /// The returned data will only contain values with those fields.
     ///
     /// # Arguments
     ///
-    /// * `page` - The number of the page to select.
+    /// * `field` - A field to at to the query.
     #[must_use]
-    pub fn with_page(self, page: u32) -> Self {
+    pub fn add_select(self, field: impl Into<SetField>) -> Self {
         let mut new = self.clone();
-        new.page = Some(page);
+        new.select_fields.push(field.into().path());
         new
     }
 
-    /// Adds a parameter to the ordering list.
+    /// Adds a filter for any field to the query parameter, if used more than once for a field it turns into an OR.
     ///
     /// # Arguments
     ///
-    /// * `ordering` - A fields to order by in the data.
+    /// * `field` - The field to filter.
+    /// * `value` - The value of the field you want to filter for.
     #[must_use]
-    pub fn add_ordering(self, ordering: Ordering) -> Self {
-        let mut new = self.clone();
-        new.order_by.push(ordering);
-        new
+    pub fn add_filter(self, field: impl Into<SetField>, value: impl Into<FilterValue>) -> Self {
+        self.add_or_update_filter(&field.into().path(), value.into())
     }
 
-    /// Adds a value to select from the requested data.
-    /// The returned data will only contain values with those fields.
+    /// Excludes sets with the value in any field from the query.
     ///
     /// # Arguments
     ///
-    /// * `field` - A field to at to the query.
+    /// * `field` - The field to filter.
+    /// * `value` - The value of the field you want to exclude.
     #[must_use]
-    pub fn add_select(self, field: &str) -> Self {
-        let mut new = self.clone();
-        new.select_fields.push(String::from(field));
-        new
+    pub fn exclude_filter(self, field: impl Into<SetField>, value: impl Into<FilterValue>) -> Self {
+        self.add_exclusion(&field.into().path(), value.into())
     }
 
     /// Adds a id to the query parameter, if used more than once it turns into an OR.
@@ -71,49 +80,135 @@ impl SetQueryBuilder {
     /// * `id` - The id of the set you want to filter for.
     #[must_use]
     pub fn add_id(self, id: &SetId) -> Self {
-        self.add_or_update_filter("id", &id.0)
+        self.add_or_update_filter("id", FilterValue::Text(id.0.clone()))
+    }
+
+    /// Adds a set id which has to match exactly to the query parameter, if used more than once it turns into an OR.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The exact set id you want to filter for.
+    #[must_use]
+    pub fn add_id_exact(self, id: &SetId) -> Self {
+        self.add_or_update_filter("id", FilterValue::exact(&id.0))
+    }
+
+    /// Excludes results with the set id from the query, if used more than once results matching any of them are excluded.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The set id you want to exclude.
+    #[must_use]
+    pub fn exclude_id(self, id: &SetId) -> Self {
+        self.add_exclusion("id", FilterValue::Text(id.0.clone()))
     }
 
     /// Adds a set name to the query parameter, if used more than once it turns into an OR.
-    /// Look at Pokemon TCG Api wiki <https://docs.pokemontcg.io/api-reference/cards/search-cards/> for a documentation about wildcards.
+    /// Look at Pokemon TCG Api wiki <https://docs.pokemontcg.io/api-reference/cards/search-cards/> for a documentation about wildcards, which are passed as `FilterValue::Wildcard`.
     ///
     /// # Arguments
     ///
     /// * `name` - The name of the set or the wildcard name you want to query for.
     #[must_use]
-    pub fn add_name(self, name: &str) -> Self {
-        self.add_or_update_filter("name", name)
+    pub fn add_name(self, name: impl Into<FilterValue>) -> Self {
+        self.add_or_update_filter("name", name.into())
+    }
+
+    /// Adds a set name which has to match exactly to the query parameter, if used more than once it turns into an OR.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The exact set name you want to filter for.
+    #[must_use]
+    pub fn add_name_exact(self, name: &str) -> Self {
+        self.add_or_update_filter("name", FilterValue::exact(name))
+    }
+
+    /// Excludes results with the set name from the query, if used more than once results matching any of them are excluded.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The set name you want to exclude.
+    #[must_use]
+    pub fn exclude_name(self, name: impl Into<FilterValue>) -> Self {
+        self.add_exclusion("name", name.into())
     }
 
     /// Adds a sub series to the query parameter, if used more than once it turns into an OR.
-    /// Look at Pokemon TCG Api wiki <https://docs.pokemontcg.io/api-reference/cards/search-cards/> for a documentation about wildcards.
+    /// Look at Pokemon TCG Api wiki <https://docs.pokemontcg.io/api-reference/cards/search-cards/> for a documentation about wildcards, which are passed as `FilterValue::Wildcard`.
     ///
     /// # Arguments
     ///
     /// * `series` - The name of the sub series or the wildcard name you want to query for.
     #[must_use]
-    pub fn add_sub_series(self, series: &str) -> Self {
-        self.add_or_update_filter("series", series)
+    pub fn add_sub_series(self, series: impl Into<FilterValue>) -> Self {
+        self.add_or_update_filter("series", series.into())
+    }
+
+    /// Adds a series which has to match exactly to the query parameter, if used more than once it turns into an OR.
+    ///
+    /// # Arguments
+    ///
+    /// * `series` - The exact series you want to filter for.
+    #[must_use]
+    pub fn add_sub_series_exact(self, series: &str) -> Self {
+        self.add_or_update_filter("series", FilterValue::exact(series))
+    }
+
+    /// Excludes results with the series from the query, if used more than once results matching any of them are excluded.
+    ///
+    /// # Arguments
+    ///
+    /// * `series` - The series you want to exclude.
+    #[must_use]
+    pub fn exclude_sub_series(self, series: impl Into<FilterValue>) -> Self {
+        self.add_exclusion("series", series.into())
     }
 
     /// Adds a ptcgo code to the query parameter, if used more than once it turns into an OR.
-    /// Look at Pokemon TCG Api wiki <https://docs.pokemontcg.io/api-reference/cards/search-cards/> for a documentation about wildcards.
+    /// Look at Pokemon TCG Api wiki <https://docs.pokemontcg.io/api-reference/cards/search-cards/> for a documentation about wildcards, which are passed as `FilterValue::Wildcard`.
     ///
     /// # Arguments
     ///
     /// * `series` - The ptcgo code you want to query for.
     #[must_use]
-    pub fn add_ptcgo_code(self, ptcgo_code: &str) -> Self {
-        self.add_or_update_filter("ptcgoCode", ptcgo_code)
+    pub fn add_ptcgo_code(self, ptcgo_code: impl Into<FilterValue>) -> Self {
+        self.add_or_update_filter("ptcgoCode", ptcgo_code.into())
     }
 
-    fn add_or_update_filter(mut self, key: &str, value: &str) -> Self {
-        if let Some(old_value) = self.filters.get_mut(key) {
-            *old_value = format!("{old_value},{value}");
-            return self;
-        }
+    /// Adds a ptcgo code which has to match exactly to the query parameter, if used more than once it turns into an OR.
+    ///
+    /// # Arguments
+    ///
+    /// * `ptcgo_code` - The exact ptcgo code you want to filter for.
+    #[must_use]
+    pub fn add_ptcgo_code_exact(self, ptcgo_code: &str) -> Self {
+        self.add_or_update_filter("ptcgoCode", FilterValue::exact(ptcgo_code))
+    }
 
-        self.filters.insert(String::from(key), String::from(value));
+    /// Excludes results with the ptcgo code from the query, if used more than once results matching any of them are excluded.
+    ///
+    /// # Arguments
+    ///
+    /// * `ptcgo_code` - The ptcgo code you want to exclude.
+    #[must_use]
+    pub fn exclude_ptcgo_code(self, ptcgo_code: impl Into<FilterValue>) -> Self {
+        self.add_exclusion("ptcgoCode", ptcgo_code.into())
+    }
+
+    fn add_or_update_filter(mut self, key: &str, value: FilterValue) -> Self {
+        self.filters
+            .entry(String::from(key))
+            .or_default()
+            .push(value);
+        self
+    }
+
+    fn add_exclusion(mut self, key: &str, value: FilterValue) -> Self {
+        self.excluded_filters
+            .entry(String::from(key))
+            .or_default()
+            .push(value);
         self
     }
 }
@@ -121,14 +216,26 @@ impl SetQueryBuilder {
 impl QueryBuilder for SetQueryBuilder {
     fn new() -> Self {
         SetQueryBuilder {
-            filters: HashMap::new(),
+            filters: BTreeMap::new(),
+            excluded_filters: BTreeMap::new(),
             page: None,
             page_size: None,
             order_by: Vec::new(),
             select_fields: Vec::new(),
+            query: None,
         }
     }
 
+    fn with_page_size(mut self, size: u8) -> Self {
+        self.page_size = Some(size);
+        self
+    }
+
+    fn with_page(mut self, page: u32) -> Self {
+        self.page = Some(page);
+        self
+    }
+
     fn page(&self) -> Option<u32> {
         self.page
     }
@@ -137,10 +244,14 @@ impl QueryBuilder for SetQueryBuilder {
         self.page_size
     }
 
-    fn filters(&self) -> HashMap<String, String> {
+    fn filters(&self) -> BTreeMap<String, Vec<FilterValue>> {
         self.filters.clone()
     }
 
+    fn excluded_filters(&self) -> BTreeMap<String, Vec<FilterValue>> {
+        self.excluded_filters.clone()
+    }
+
     fn order_by(&self) -> Vec<Ordering> {
         self.order_by.clone()
     }
@@ -148,4 +259,40 @@ impl QueryBuilder for SetQueryBuilder {
     fn select_fields(&self) -> Vec<String> {
         self.select_fields.clone()
     }
+
+    fn query(&self) -> Option<Query> {
+        self.query.clone()
+    }
+
+    fn with_query(mut self, query: Query) -> Self {
+        self.query = Some(match self.query {
+            Some(existing) => existing.and(query),
+            None => query,
+        });
+        self
+    }
+}
+
+impl From<SetQueryBuilder> for SavedSearch {
+    fn from(builder: SetQueryBuilder) -> Self {
+        SavedSearch::from_builder(SearchKind::Set, &builder)
+    }
+}
+
+impl TryFrom<SavedSearch> for SetQueryBuilder {
+    type Error = SavedSearchError;
+
+    fn try_from(search: SavedSearch) -> Result<Self, Self::Error> {
+        let parts = search.into_parts(SearchKind::Set)?;
+
+        Ok(SetQueryBuilder {
+            filters: parts.filters,
+            excluded_filters: parts.excluded_filters,
+            page: parts.page,
+            page_size: parts.page_size,
+            order_by: parts.order_by,
+            select_fields: parts.select_fields,
+            query: parts.query,
+        })
+    }
 }
@@ -1,67 +1,80 @@
-use std::collections::HashMap;
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
 
 use crate::pokemon_api_client::api_client::CardId;
 
-use super::{Ordering, QueryBuilder};
+use super::{
+    field::CardField,
+    query::Query,
+    saved::{SavedSearch, SavedSearchError, SearchKind},
+    FilterValue, Ordering, QueryBuilder,
+};
 
 /// Query builder for trainer cards.
-#[derive(Clone)]
+/// Serializes into and deserializes from a `SavedSearch`.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(into = "SavedSearch", try_from = "SavedSearch")]
 pub struct TrainerQueryBuilder {
-    filters: HashMap<String, String>,
+    filters: BTreeMap<String, Vec<FilterValue>>,
+    excluded_filters: BTreeMap<String, Vec<FilterValue>>,
     page: Option<u32>,
     page_size: Option<u8>,
     order_by: Vec<Ordering>,
     select_fields: Vec<String>,
+    query: Option<Query>,
 }
 
 impl TrainerQueryBuilder {
-    /// Sets the page size of the query builder.
+    /// Adds a parameter to the ordering list.
     ///
     /// # Arguments
     ///
-    /// * `size` - The size of the page.
+    /// * `ordering` - A fields to order by in the data.
     #[must_use]
-    pub fn with_page_size(self, size: u8) -> Self {
+    pub fn add_ordering(self, ordering: Ordering) -> Self {
         let mut new = self.clone();
-        new.page_size = Some(size);
+        new.order_by.push(ordering);
         new
     }
 
-    /// Sets the page from which it should get the results.
+    /// Adds a value to select from the requested data.
+    /// The returned data will only contain values with those fields.
     ///
     /// # Arguments
     ///
-    /// * `page` - The number of the page to select.
+    /// * `field` - A field to at to the query.
     #[must_use]
-    pub fn with_page(self, page: u32) -> Self {
+    pub fn add_select(self, field: impl Into<CardField>) -> Self {
         let mut new = self.clone();
-        new.page = Some(page);
+        new.select_fields.push(field.into().path());
         new
     }
 
-    /// Adds a parameter to the ordering list.
+    /// Adds a filter for any field to the query parameter, if used more than once for a field it turns into an OR.
     ///
     /// # Arguments
     ///
-    /// * `ordering` - A fields to order by in the data.
+    /// * `field` - The field to filter.
+    /// * `value` - The value of the field you want to filter for.
     #[must_use]
-    pub fn add_ordering(self, ordering: Ordering) -> Self {
-        let mut new = self.clone();
-        new.order_by.push(ordering);
-        new
+    pub fn add_filter(self, field: impl Into<CardField>, value: impl Into<FilterValue>) -> Self {
+        self.add_or_update_filter(&field.into().path(), value.into())
     }
 
-    /// Adds a value to select from the requested data.
-    /// The returned data will only contain values with those fields.
+    /// Excludes trainer cards with the value in any field from the query.
     ///
     /// # Arguments
     ///
-    /// * `field` - A field to at to the query.
+    /// * `field` - The field to filter.
+    /// * `value` - The value of the field you want to exclude.
     #[must_use]
-    pub fn add_select(self, field: &str) -> Self {
-        let mut new = self.clone();
-        new.select_fields.push(String::from(field));
-        new
+    pub fn exclude_filter(
+        self,
+        field: impl Into<CardField>,
+        value: impl Into<FilterValue>,
+    ) -> Self {
+        self.add_exclusion(&field.into().path(), value.into())
     }
 
     /// Adds a id to the query parameter, if used more than once it turns into an OR.
@@ -71,38 +84,104 @@ impl TrainerQueryBuilder {
     /// * `id` - The id of the trainer card you want to filter for.
     #[must_use]
     pub fn add_id(self, id: &CardId) -> Self {
-        self.add_or_update_filter("id", &id.0)
+        self.add_or_update_filter("id", FilterValue::Text(id.0.clone()))
+    }
+
+    /// Adds a card id which has to match exactly to the query parameter, if used more than once it turns into an OR.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The exact card id you want to filter for.
+    #[must_use]
+    pub fn add_id_exact(self, id: &CardId) -> Self {
+        self.add_or_update_filter("id", FilterValue::exact(&id.0))
+    }
+
+    /// Excludes results with the card id from the query, if used more than once results matching any of them are excluded.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The card id you want to exclude.
+    #[must_use]
+    pub fn exclude_id(self, id: &CardId) -> Self {
+        self.add_exclusion("id", FilterValue::Text(id.0.clone()))
     }
 
     /// Adds a card name to the query parameter, if used more than once it turns into an OR.
-    /// Look at Pokemon TCG Api wiki <https://docs.pokemontcg.io/api-reference/cards/search-cards/> for a documentation about wildcards.
+    /// Look at Pokemon TCG Api wiki <https://docs.pokemontcg.io/api-reference/cards/search-cards/> for a documentation about wildcards, which are passed as `FilterValue::Wildcard`.
     ///
     /// # Arguments
     ///
     /// * `name` - The name of the trainer card or the wildcard name you want to query for.
     #[must_use]
-    pub fn add_name(self, name: &str) -> Self {
-        self.add_or_update_filter("name", name)
+    pub fn add_name(self, name: impl Into<FilterValue>) -> Self {
+        self.add_or_update_filter("name", name.into())
+    }
+
+    /// Adds a card name which has to match exactly to the query parameter, if used more than once it turns into an OR.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The exact card name you want to filter for.
+    #[must_use]
+    pub fn add_name_exact(self, name: &str) -> Self {
+        self.add_or_update_filter("name", FilterValue::exact(name))
+    }
+
+    /// Excludes results with the card name from the query, if used more than once results matching any of them are excluded.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The card name you want to exclude.
+    #[must_use]
+    pub fn exclude_name(self, name: impl Into<FilterValue>) -> Self {
+        self.add_exclusion("name", name.into())
     }
 
     /// Adds a card set series to the query parameter, if used more than once it turns into an OR.
-    /// Look at Pokemon TCG Api wiki <https://docs.pokemontcg.io/api-reference/cards/search-cards/> for a documentation about wildcards.
+    /// Look at Pokemon TCG Api wiki <https://docs.pokemontcg.io/api-reference/cards/search-cards/> for a documentation about wildcards, which are passed as `FilterValue::Wildcard`.
     ///
     /// # Arguments
     ///
     /// * `set_series` - The set series of the card you want to query for.
     #[must_use]
-    pub fn add_set_series(self, set_series: &str) -> Self {
-        self.add_or_update_filter("set.series", set_series)
+    pub fn add_set_series(self, set_series: impl Into<FilterValue>) -> Self {
+        self.add_or_update_filter("set.series", set_series.into())
     }
 
-    fn add_or_update_filter(mut self, key: &str, value: &str) -> Self {
-        if let Some(old_value) = self.filters.get_mut(key) {
-            *old_value = format!("{old_value},{value}");
-            return self;
-        }
+    /// Adds a set series which has to match exactly to the query parameter, if used more than once it turns into an OR.
+    ///
+    /// # Arguments
+    ///
+    /// * `set_series` - The exact set series you want to filter for.
+    #[must_use]
+    pub fn add_set_series_exact(self, set_series: &str) -> Self {
+        self.add_or_update_filter("set.series", FilterValue::exact(set_series))
+    }
+
+    /// Excludes results with the set series from the query, if used more than once results matching any of them are excluded.
+    ///
+    /// # Arguments
+    ///
+    /// * `set_series` - The set series you want to exclude.
+    #[must_use]
+    pub fn exclude_set_series(self, set_series: impl Into<FilterValue>) -> Self {
+        self.add_exclusion("set.series", set_series.into())
+    }
 
-        self.filters.insert(String::from(key), String::from(value));
+    fn add_or_update_filter(mut self, key: &str, value: FilterValue) -> Self {
+        self.filters
+            .entry(String::from(key))
+            .or_default()
+            .push(value);
+        self
+    }
+
+    fn add_exclusion(mut self, key: &str, value: FilterValue) -> Self {
+        self.excluded_filters
+            .entry(String::from(key))
+            .or_default()
+            .push(value);
         self
     }
 }
@@ -110,14 +189,26 @@ impl TrainerQueryBuilder {
 impl QueryBuilder for TrainerQueryBuilder {
     fn new() -> Self {
         TrainerQueryBuilder {
-            filters: HashMap::new(),
+            filters: BTreeMap::new(),
+            excluded_filters: BTreeMap::new(),
             page: None,
             page_size: None,
             order_by: Vec::new(),
             select_fields: Vec::new(),
+            query: None,
         }
     }
 
+    fn with_page_size(mut self, size: u8) -> Self {
+        self.page_size = Some(size);
+        self
+    }
+
+    fn with_page(mut self, page: u32) -> Self {
+        self.page = Some(page);
+        self
+    }
+
     fn page(&self) -> Option<u32> {
         self.page
     }
@@ -126,10 +217,14 @@ impl QueryBuilder for TrainerQueryBuilder {
         self.page_size
     }
 
-    fn filters(&self) -> HashMap<String, String> {
+    fn filters(&self) -> BTreeMap<String, Vec<FilterValue>> {
         self.filters.clone()
     }
 
+    fn excluded_filters(&self) -> BTreeMap<String, Vec<FilterValue>> {
+        self.excluded_filters.clone()
+    }
+
     fn order_by(&self) -> Vec<Ordering> {
         self.order_by.clone()
     }
@@ -137,4 +232,40 @@ impl QueryBuilder for TrainerQueryBuilder {
     fn select_fields(&self) -> Vec<String> {
         self.select_fields.clone()
     }
+
+    fn query(&self) -> Option<Query> {
+        self.query.clone()
+    }
+
+    fn with_query(mut self, query: Query) -> Self {
+        self.query = Some(match self.query {
+            Some(existing) => existing.and(query),
+            None => query,
+        });
+        self
+    }
+}
+
+impl From<TrainerQueryBuilder> for SavedSearch {
+    fn from(builder: TrainerQueryBuilder) -> Self {
+        SavedSearch::from_builder(SearchKind::Trainer, &builder)
+    }
+}
+
+impl TryFrom<SavedSearch> for TrainerQueryBuilder {
+    type Error = SavedSearchError;
+
+    fn try_from(search: SavedSearch) -> Result<Self, Self::Error> {
+        let parts = search.into_parts(SearchKind::Trainer)?;
+
+        Ok(TrainerQueryBuilder {
+            filters: parts.filters,
+            excluded_filters: parts.excluded_filters,
+            page: parts.page,
+            page_size: parts.page_size,
+            order_by: parts.order_by,
+            select_fields: parts.select_fields,
+            query: parts.query,
+        })
+    }
 }
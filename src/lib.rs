@@ -4,13 +4,25 @@
 //! Can either query by id or resources a bit more specific.
 //!
 //! Features:
-//! - Central api client
+//! - Central api client, configurable through a builder
+//! - Pluggable http transport, with an in memory transport for testing
+//! - Optional retries, rate limiting, timeouts and response caching
 //! - Methods for all api paths
+//! - Paged results with the pagination metadata of the api
+//! - Streams over all results of a query
 //! - Query builders for the advanced querying of data
 //!     - Pokemon cards
 //!     - Sets
 //!     - Trainer cards
 //!     - Energies
+//! - Saved searches, serializing the query builders into a versioned format, e.g. json or toml
+//! - Sync of the whole catalogue into a local snapshot with incremental updates
+//! - Offline access to a synced snapshot, including local evaluation of the query builders
+//! - Normalized sqlite storage of the catalogue (`sqlite` feature)
 pub mod builder;
+pub mod offline;
 pub mod pokemon_api_client;
 pub mod resource;
+#[cfg(feature = "sqlite")]
+pub mod storage;
+pub mod sync;
@@ -0,0 +1,423 @@
+//! Contains the offline data source, answering requests from a snapshot created by the `sync` module.
+pub mod query;
+
+use std::{collections::HashMap, path::Path};
+
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::Value;
+
+use crate::{
+    builder::QueryBuilder,
+    pokemon_api_client::{
+        api_client::{CardId, SetId},
+        error::ApiError,
+        page::Page,
+    },
+    resource::{card::Card, set::Set, ApiResource},
+    sync::manifest::{
+        cards_path, metadata_path, read_json, sets_path, SnapshotManifest, SnapshotMetadata,
+    },
+};
+
+/// Offline replacement for the `PokemonApiClient`, holding a whole snapshot in memory.
+/// Never attempts to access the network, data missing in the snapshot results in `ApiError::NotInSnapshot`.
+/// The resources are held as json only, which is what the local queries are evaluated on,
+/// and converted into their structs when returned.
+#[derive(Debug, Clone)]
+pub struct OfflineClient {
+    manifest: SnapshotManifest,
+    metadata: Option<SnapshotMetadata>,
+    sets: Vec<Value>,
+    cards: Vec<Value>,
+    set_indices: HashMap<String, usize>,
+    card_indices: HashMap<String, usize>,
+}
+
+impl OfflineClient {
+    /// Loads a snapshot directory written by a `CatalogueSync`.
+    ///
+    /// # Arguments
+    ///
+    /// * `directory` - The snapshot directory.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the directory contains no snapshot or one of its files cannot be parsed.
+    pub fn load(directory: impl AsRef<Path>) -> Result<Self, ApiError> {
+        let directory = directory.as_ref();
+
+        let manifest = SnapshotManifest::load(directory).map_err(|err| {
+            ApiError::NotInSnapshot(format!(
+                "No snapshot manifest in {}: {err}",
+                directory.display()
+            ))
+        })?;
+        let metadata_path = metadata_path(directory);
+        let metadata = if metadata_path.exists() {
+            Some(read_json(&metadata_path)?)
+        } else {
+            None
+        };
+        let sets: Vec<Set> = read_json(&sets_path(directory))?;
+
+        let mut cards = Vec::with_capacity(manifest.card_count);
+        for set_id in manifest.sets.keys() {
+            let path = cards_path(directory, set_id);
+
+            if path.exists() {
+                let set_cards: Vec<Card> = read_json(&path)?;
+                cards.extend(set_cards);
+            }
+        }
+
+        Self::from_parts(manifest, metadata, sets, cards)
+    }
+
+    /// Creates an offline client from already loaded data.
+    /// The resources are converted to json with the field names of the api.
+    ///
+    /// # Arguments
+    ///
+    /// * `manifest` - The manifest describing the data.
+    /// * `metadata` - The lists of the metadata endpoints, if available.
+    /// * `sets` - All sets.
+    /// * `cards` - All cards.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the resources cannot be converted to json.
+    pub fn from_parts(
+        manifest: SnapshotManifest,
+        metadata: Option<SnapshotMetadata>,
+        sets: Vec<Set>,
+        cards: Vec<Card>,
+    ) -> Result<Self, ApiError> {
+        let set_indices = sets
+            .iter()
+            .enumerate()
+            .map(|(index, set)| (set.id.clone(), index))
+            .collect();
+        let card_indices = cards
+            .iter()
+            .enumerate()
+            .map(|(index, card)| (card.id.clone(), index))
+            .collect();
+
+        let sets = sets
+            .iter()
+            .map(serde_json::to_value)
+            .collect::<Result<Vec<Value>, serde_json::Error>>()?;
+        let cards = cards
+            .iter()
+            .map(serde_json::to_value)
+            .collect::<Result<Vec<Value>, serde_json::Error>>()?;
+
+        Ok(OfflineClient {
+            manifest,
+            metadata,
+            sets,
+            cards,
+            set_indices,
+            card_indices,
+        })
+    }
+
+    /// Returns the manifest of the loaded snapshot.
+    #[must_use]
+    pub fn manifest(&self) -> &SnapshotManifest {
+        &self.manifest
+    }
+
+    /// Gets results from the snapshot based on a `QueryBuilder`, like the api would return them.
+    ///
+    /// # Arguments
+    ///
+    /// * `query_builder` - The query builder which creates the query parameters.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the resource is not part of the snapshot or the results cannot be converted into `T`.
+    pub fn get_queryable_resources<T, Q>(&self, query_builder: Q) -> Result<T, ApiError>
+    where
+        T: DeserializeOwned + ApiResource,
+        Q: QueryBuilder,
+    {
+        let page: Page<Value> = query::evaluate_values(self.values_for::<T>()?, &query_builder)?;
+
+        Ok(serde_json::from_value(Value::Array(page.data))?)
+    }
+
+    /// Gets a single page of results from the snapshot based on a `QueryBuilder`,
+    /// including the pagination metadata.
+    ///
+    /// # Arguments
+    ///
+    /// * `query_builder` - The query builder which creates the query parameters.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the resource is not part of the snapshot or the results cannot be converted into `T`.
+    pub fn get_queryable_resources_page<T, Q>(&self, query_builder: Q) -> Result<Page<T>, ApiError>
+    where
+        T: DeserializeOwned + ApiResource,
+        Q: QueryBuilder,
+    {
+        query::evaluate_values(self.values_for::<T>()?, &query_builder)
+    }
+
+    fn values_for<T: ApiResource>(&self) -> Result<&[Value], ApiError> {
+        let path = T::path();
+
+        if path == Card::path() {
+            Ok(&self.cards)
+        } else if path == Set::path() {
+            Ok(&self.sets)
+        } else {
+            Err(ApiError::NotInSnapshot(path))
+        }
+    }
+
+    /// Gets a card from the snapshot based on its id.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The id of the card to retrieve.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the card is not part of the snapshot or cannot be converted into a `Card`.
+    pub fn get_card(&self, id: CardId) -> Result<Card, ApiError> {
+        let index = self
+            .card_indices
+            .get(&id.0)
+            .ok_or_else(|| ApiError::NotInSnapshot(format!("card {}", id.0)))?;
+
+        Ok(Card::deserialize(&self.cards[*index])?)
+    }
+
+    /// Gets a list of all cards from the snapshot.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the snapshot contains no cards or they cannot be converted into `Card`s.
+    pub fn get_all_cards(&self) -> Result<Vec<Card>, ApiError> {
+        if self.cards.is_empty() {
+            return Err(ApiError::NotInSnapshot(String::from("cards")));
+        }
+
+        Ok(self
+            .cards
+            .iter()
+            .map(Card::deserialize)
+            .collect::<Result<Vec<Card>, serde_json::Error>>()?)
+    }
+
+    /// Gets a set from the snapshot based on its id.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The id of the set to retrieve.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the set is not part of the snapshot or cannot be converted into a `Set`.
+    pub fn get_set(&self, id: SetId) -> Result<Set, ApiError> {
+        let index = self
+            .set_indices
+            .get(&id.0)
+            .ok_or_else(|| ApiError::NotInSnapshot(format!("set {}", id.0)))?;
+
+        Ok(Set::deserialize(&self.sets[*index])?)
+    }
+
+    /// Gets a list of all sets from the snapshot.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the snapshot contains no sets or they cannot be converted into `Set`s.
+    pub fn get_all_sets(&self) -> Result<Vec<Set>, ApiError> {
+        if self.sets.is_empty() {
+            return Err(ApiError::NotInSnapshot(String::from("sets")));
+        }
+
+        Ok(self
+            .sets
+            .iter()
+            .map(Set::deserialize)
+            .collect::<Result<Vec<Set>, serde_json::Error>>()?)
+    }
+
+    /// Gets a list of all types from the snapshot.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the snapshot contains no metadata.
+    pub fn get_all_types(&self) -> Result<Vec<String>, ApiError> {
+        self.metadata("types", |metadata| &metadata.types)
+    }
+
+    /// Gets a list of all subtypes from the snapshot.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the snapshot contains no metadata.
+    pub fn get_all_subtype(&self) -> Result<Vec<String>, ApiError> {
+        self.metadata("subtypes", |metadata| &metadata.subtypes)
+    }
+
+    /// Gets a list of all supertypes from the snapshot.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the snapshot contains no metadata.
+    pub fn get_all_supertypes(&self) -> Result<Vec<String>, ApiError> {
+        self.metadata("supertypes", |metadata| &metadata.supertypes)
+    }
+
+    /// Gets a list of all rarities from the snapshot.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the snapshot contains no metadata.
+    pub fn get_all_rarities(&self) -> Result<Vec<String>, ApiError> {
+        self.metadata("rarities", |metadata| &metadata.rarities)
+    }
+
+    fn metadata(
+        &self,
+        name: &str,
+        select: impl Fn(&SnapshotMetadata) -> &Vec<String>,
+    ) -> Result<Vec<String>, ApiError> {
+        self.metadata
+            .as_ref()
+            .map(|metadata| select(metadata).clone())
+            .ok_or_else(|| ApiError::NotInSnapshot(String::from(name)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::PathBuf, process};
+
+    use super::*;
+    use crate::{
+        builder::{field::CardField, pokemon::PokemonQueryBuilder},
+        sync::manifest::cards_directory,
+    };
+
+    fn snapshot_directory(name: &str) -> PathBuf {
+        let directory =
+            std::env::temp_dir().join(format!("pokemon-tcg-offline-{name}-{}", process::id()));
+        let _ = fs::remove_dir_all(&directory);
+        fs::create_dir_all(cards_directory(&directory)).unwrap();
+
+        directory
+    }
+
+    fn write_snapshot(directory: &Path) {
+        fs::write(
+            directory.join("manifest.json"),
+            r#"{
+                "version": 1,
+                "created_at": 1600000000,
+                "synced_at": 1600000000,
+                "set_count": 1,
+                "card_count": 2,
+                "sets": {
+                    "swsh4": { "updated_at": "2020/11/13 16:20:00", "card_count": 2, "synced_at": 1600000000 }
+                }
+            }"#,
+        )
+        .unwrap();
+        fs::write(
+            directory.join("metadata.json"),
+            r#"{ "types": ["Fire"], "subtypes": ["Basic"], "supertypes": ["Pokémon"], "rarities": ["Rare"] }"#,
+        )
+        .unwrap();
+        fs::write(
+            directory.join("sets.json"),
+            r#"[{
+                "id": "swsh4", "name": "Vivid Voltage", "series": "Sword & Shield", "printedTotal": 185, "total": 203,
+                "legalities": { "unlimited": "Legal" },
+                "releaseDate": "2020/11/13", "updatedAt": "2020/11/13 16:20:00"
+            }]"#,
+        )
+        .unwrap();
+
+        let card = |id: &str, name: &str, mark: &str| {
+            format!(
+                r#"{{
+                    "id": "{id}", "name": "{name}", "supertype": "Pokémon",
+                    "set": {{
+                        "id": "swsh4", "name": "Vivid Voltage", "series": "Sword & Shield", "printedTotal": 185, "total": 203,
+                        "legalities": {{ "unlimited": "Legal" }},
+                        "releaseDate": "2020/11/13", "updatedAt": "2020/11/13 16:20:00"
+                    }},
+                    "number": "1",
+                    "legalities": {{ "unlimited": "Legal" }},
+                    "regulationsMark": "{mark}",
+                    "images": {{ "small": "small.png", "large": "large.png" }}
+                }}"#
+            )
+        };
+        fs::write(
+            cards_path(directory, "swsh4"),
+            format!(
+                "[{}, {}]",
+                card("swsh4-25", "Charmander", "D"),
+                card("swsh4-26", "Charmeleon", "E")
+            ),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn loads_snapshot_from_directory() {
+        let directory = snapshot_directory("load");
+        write_snapshot(&directory);
+
+        let client = OfflineClient::load(&directory).unwrap();
+
+        assert_eq!(client.manifest().card_count, 2);
+        assert_eq!(
+            client
+                .get_card(CardId(String::from("swsh4-26")))
+                .unwrap()
+                .name,
+            "Charmeleon"
+        );
+        assert_eq!(
+            client.get_set(SetId(String::from("swsh4"))).unwrap().name,
+            "Vivid Voltage"
+        );
+        assert_eq!(client.get_all_cards().unwrap().len(), 2);
+        assert_eq!(client.get_all_sets().unwrap().len(), 1);
+        assert_eq!(client.get_all_types().unwrap(), vec!["Fire"]);
+        assert!(matches!(
+            client.get_card(CardId(String::from("base1-4"))),
+            Err(ApiError::NotInSnapshot(_))
+        ));
+
+        let cards: Vec<Card> = client
+            .get_queryable_resources(
+                PokemonQueryBuilder::new().add_filter(CardField::RegulationMark, "e"),
+            )
+            .unwrap();
+        assert_eq!(cards.len(), 1);
+        assert_eq!(cards[0].id, "swsh4-26");
+
+        let _ = fs::remove_dir_all(&directory);
+    }
+
+    #[test]
+    fn rejects_directory_without_snapshot() {
+        let directory = snapshot_directory("missing");
+
+        assert!(matches!(
+            OfflineClient::load(&directory),
+            Err(ApiError::NotInSnapshot(_))
+        ));
+
+        let _ = fs::remove_dir_all(&directory);
+    }
+}
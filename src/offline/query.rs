@@ -0,0 +1,608 @@
+use std::cmp::Ordering as CmpOrdering;
+
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{Map, Value};
+
+use crate::{
+    builder::{query::Query, Ordering, QueryBuilder},
+    pokemon_api_client::{error::ApiError, page::Page},
+};
+
+/// Page size the api uses if a query does not set one.
+static DEFAULT_PAGE_SIZE: u32 = 250;
+
+/// Evaluates a query builder against in memory resources with the semantics of the api:
+/// filters, ordering, paging and selected fields.
+/// Field names are the json names of the api, e.g. `set.series` or `attacks.convertedEnergyCost`.
+///
+/// # Arguments
+///
+/// * `items` - The resources to query, e.g. all cards of a snapshot.
+/// * `query_builder` - The query builder holding the filters, ordering, paging and selected fields.
+///
+/// # Errors
+///
+/// Will return `Err` if the results cannot be converted into `T`.
+pub fn evaluate_query<R, T, Q>(items: &[R], query_builder: &Q) -> Result<Page<T>, ApiError>
+where
+    R: Serialize,
+    T: DeserializeOwned,
+    Q: QueryBuilder,
+{
+    let values = items
+        .iter()
+        .map(serde_json::to_value)
+        .collect::<Result<Vec<Value>, serde_json::Error>>()?;
+
+    evaluate_values(&values, query_builder)
+}
+
+/// Orders resources from a local source the same way the api orders them for `orderBy`.
+/// Ties keep their previous order and resources missing a field are sorted last.
+///
+/// # Arguments
+///
+/// * `items` - The resources to order, e.g. cards read from a snapshot.
+/// * `order_by` - The orderings, applied one after another for ties.
+///
+/// # Errors
+///
+/// Will return `Err` if the resources cannot be converted to json.
+pub fn order_resources<R: Serialize>(
+    items: &mut Vec<R>,
+    order_by: &[Ordering],
+) -> Result<(), ApiError> {
+    if order_by.is_empty() {
+        return Ok(());
+    }
+
+    let mut keyed = std::mem::take(items)
+        .into_iter()
+        .map(|item| Ok((serde_json::to_value(&item)?, item)))
+        .collect::<Result<Vec<(Value, R)>, ApiError>>()?;
+
+    sort_values(&mut keyed, order_by, |(value, _)| value);
+    items.extend(keyed.into_iter().map(|(_, item)| item));
+
+    Ok(())
+}
+
+/// Evaluates a query builder against resources already converted to json values.
+pub(crate) fn evaluate_values<T, Q>(
+    values: &[Value],
+    query_builder: &Q,
+) -> Result<Page<T>, ApiError>
+where
+    T: DeserializeOwned,
+    Q: QueryBuilder,
+{
+    let matcher = query_builder.to_query().as_ref().map(Matcher::compile);
+
+    let mut matches = values
+        .iter()
+        .filter(|value| {
+            matcher
+                .as_ref()
+                .is_none_or(|matcher| matcher.matches(value))
+        })
+        .collect::<Vec<&Value>>();
+
+    sort_values(&mut matches, &query_builder.order_by(), |value| value);
+
+    let page = query_builder.page().unwrap_or(1).max(1);
+    let page_size = query_builder
+        .page_size()
+        .map_or(DEFAULT_PAGE_SIZE, u32::from)
+        .max(1);
+    let start = (page as usize - 1).saturating_mul(page_size as usize);
+    let select_fields = query_builder.select_fields();
+
+    let data = matches
+        .iter()
+        .skip(start)
+        .take(page_size as usize)
+        .map(|value| serde_json::from_value(select(value, &select_fields)))
+        .collect::<Result<Vec<T>, serde_json::Error>>()?;
+
+    Ok(Page {
+        page,
+        page_size,
+        count: u32::try_from(data.len()).unwrap_or(u32::MAX),
+        total_count: u32::try_from(matches.len()).unwrap_or(u32::MAX),
+        data,
+    })
+}
+
+/// A query compiled for matching it against many json values.
+enum Matcher {
+    /// At least one value of the field has to match the term.
+    Field {
+        path: Vec<String>,
+        term: Term,
+    },
+    And(Vec<Matcher>),
+    Or(Vec<Matcher>),
+    Not(Box<Matcher>),
+}
+
+/// A single value of a filter.
+enum Term {
+    /// Matches values containing the words of the text in the same order.
+    Text(Vec<String>),
+    /// Matches values or words of values with `*` and `?` placeholders.
+    Wildcard(String),
+    /// Matches values which are exactly the text, ignoring the case.
+    Exact(String),
+    /// Matches values between the bounds, `None` is unbounded.
+    Range {
+        low: Option<String>,
+        high: Option<String>,
+        inclusive: bool,
+    },
+}
+
+impl Matcher {
+    fn compile(query: &Query) -> Self {
+        match query {
+            Query::Term { field, value } => Matcher::field(field, Term::Text(tokenize(value))),
+            Query::Wildcard { field, pattern } => {
+                Matcher::field(field, Term::Wildcard(pattern.to_lowercase()))
+            }
+            Query::Exact { field, value } => {
+                Matcher::field(field, Term::Exact(value.to_lowercase()))
+            }
+            Query::Range {
+                field,
+                low,
+                high,
+                inclusive,
+            } => Matcher::field(
+                field,
+                Term::Range {
+                    low: range_bound(low),
+                    high: range_bound(high),
+                    inclusive: *inclusive,
+                },
+            ),
+            Query::Raw { field, value } => Matcher::compile(&Query::from_raw(field, value)),
+            Query::And(queries) => Matcher::And(queries.iter().map(Matcher::compile).collect()),
+            Query::Or(queries) => Matcher::Or(queries.iter().map(Matcher::compile).collect()),
+            Query::Not(query) => Matcher::Not(Box::new(Matcher::compile(query))),
+            Query::Group(query) => Matcher::compile(query),
+        }
+    }
+
+    fn field(field: &str, term: Term) -> Self {
+        Matcher::Field {
+            path: split_path(field),
+            term,
+        }
+    }
+
+    fn matches(&self, value: &Value) -> bool {
+        match self {
+            Matcher::Field { path, term } => leaf_values(value, path)
+                .iter()
+                .any(|leaf| term.matches(leaf)),
+            Matcher::And(matchers) => matchers.iter().all(|matcher| matcher.matches(value)),
+            Matcher::Or(matchers) => matchers.iter().any(|matcher| matcher.matches(value)),
+            Matcher::Not(matcher) => !matcher.matches(value),
+        }
+    }
+}
+
+impl Term {
+    fn matches(&self, leaf: &str) -> bool {
+        match self {
+            Term::Text(words) => {
+                let leaf_words = tokenize(leaf);
+
+                !words.is_empty()
+                    && leaf_words
+                        .windows(words.len())
+                        .any(|window| window == words.as_slice())
+            }
+            Term::Exact(text) => leaf.to_lowercase() == *text,
+            Term::Wildcard(pattern) => {
+                let leaf = leaf.to_lowercase();
+
+                wildcard_matches(pattern, &leaf)
+                    || leaf
+                        .split_whitespace()
+                        .any(|word| wildcard_matches(pattern, word))
+            }
+            Term::Range {
+                low,
+                high,
+                inclusive,
+            } => {
+                let above_low = low.as_deref().is_none_or(|low| {
+                    let ordering = compare_scalars(leaf, low);
+                    ordering == CmpOrdering::Greater
+                        || (*inclusive && ordering == CmpOrdering::Equal)
+                });
+                let below_high = high.as_deref().is_none_or(|high| {
+                    let ordering = compare_scalars(leaf, high);
+                    ordering == CmpOrdering::Less || (*inclusive && ordering == CmpOrdering::Equal)
+                });
+
+                above_low && below_high
+            }
+        }
+    }
+}
+
+fn range_bound(bound: &str) -> Option<String> {
+    let bound = bound.trim();
+
+    (bound != "*").then(|| bound.to_owned())
+}
+
+/// Splits a text into lowercase words the same way for filters and values.
+fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !(c.is_alphanumeric() || c == '\''))
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Matches a text against a pattern where `*` stands for any amount and `?` for one character.
+fn wildcard_matches(pattern: &str, text: &str) -> bool {
+    let pattern = pattern.chars().collect::<Vec<char>>();
+    let text = text.chars().collect::<Vec<char>>();
+
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, t));
+            p += 1;
+        } else if let Some((star, matched)) = backtrack {
+            p = star + 1;
+            t = matched + 1;
+            backtrack = Some((star, matched + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+/// Compares two scalars numerically if both are numbers and case insensitive as text otherwise.
+fn compare_scalars(left: &str, right: &str) -> CmpOrdering {
+    match (left.parse::<f64>(), right.parse::<f64>()) {
+        (Ok(left), Ok(right)) => left.partial_cmp(&right).unwrap_or(CmpOrdering::Equal),
+        _ => left.to_lowercase().cmp(&right.to_lowercase()),
+    }
+}
+
+/// Collects the scalar values at the path, descending into every element of arrays.
+fn leaf_values(value: &Value, path: &[String]) -> Vec<String> {
+    let mut leaves = Vec::new();
+    collect_leaves(value, path, &mut leaves);
+    leaves
+}
+
+fn collect_leaves(value: &Value, path: &[String], leaves: &mut Vec<String>) {
+    match value {
+        Value::Array(items) => {
+            for item in items {
+                collect_leaves(item, path, leaves);
+            }
+        }
+        Value::Object(map) => {
+            if let Some((first, rest)) = path.split_first() {
+                if let Some(child) = map.get(first) {
+                    collect_leaves(child, rest, leaves);
+                }
+            }
+        }
+        Value::String(text) if path.is_empty() => leaves.push(text.clone()),
+        Value::Number(number) if path.is_empty() => leaves.push(number.to_string()),
+        Value::Bool(flag) if path.is_empty() => leaves.push(flag.to_string()),
+        _ => {}
+    }
+}
+
+/// Sorts the items by the orderings of their json values, values missing a field are always sorted last.
+pub(crate) fn sort_values<I>(
+    items: &mut [I],
+    order_by: &[Ordering],
+    value_of: impl Fn(&I) -> &Value,
+) {
+    if order_by.is_empty() {
+        return;
+    }
+
+    let keys = order_by
+        .iter()
+        .map(|ordering| (split_path(ordering.field()), ordering.is_descending()))
+        .collect::<Vec<(Vec<String>, bool)>>();
+
+    items.sort_by(|left, right| {
+        let (left, right) = (value_of(left), value_of(right));
+
+        for (path, descending) in &keys {
+            let left = leaf_values(left, path).into_iter().next();
+            let right = leaf_values(right, path).into_iter().next();
+
+            let ordering = match (left, right) {
+                (Some(left), Some(right)) if *descending => compare_scalars(&right, &left),
+                (Some(left), Some(right)) => compare_scalars(&left, &right),
+                (Some(_), None) => CmpOrdering::Less,
+                (None, Some(_)) => CmpOrdering::Greater,
+                (None, None) => CmpOrdering::Equal,
+            };
+
+            if ordering != CmpOrdering::Equal {
+                return ordering;
+            }
+        }
+
+        CmpOrdering::Equal
+    });
+}
+
+fn split_path(field: &str) -> Vec<String> {
+    field.split('.').map(str::to_owned).collect()
+}
+
+/// Keeps only the selected fields of a value, all fields if none are selected.
+fn select(value: &Value, fields: &[String]) -> Value {
+    if fields.is_empty() {
+        return value.clone();
+    }
+
+    let mut selected = Map::new();
+
+    for field in fields {
+        copy_path(value, &split_path(field), &mut selected);
+    }
+
+    Value::Object(selected)
+}
+
+fn copy_path(source: &Value, path: &[String], target: &mut Map<String, Value>) {
+    let Some((first, rest)) = path.split_first() else {
+        return;
+    };
+    let Some(child) = source.get(first) else {
+        return;
+    };
+
+    if rest.is_empty() || !child.is_object() {
+        target.insert(first.clone(), child.clone());
+        return;
+    }
+
+    let entry = target
+        .entry(first.clone())
+        .or_insert_with(|| Value::Object(Map::new()));
+
+    if let Value::Object(nested) = entry {
+        copy_path(child, rest, nested);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        builder::{
+            field::{
+                CardField, CardmarketPriceKind, SetField, TcgplayerPriceKind, TcgplayerVariant,
+            },
+            pokemon::PokemonQueryBuilder,
+            FilterValue,
+        },
+        pokemon_api_client::api_client::CardId,
+        resource::card::Card,
+    };
+
+    fn cards() -> Vec<Card> {
+        serde_json::from_str(
+            r#"[
+            {
+                "id": "base1-4",
+                "name": "Charizard",
+                "supertype": "Pokémon",
+                "hp": "120",
+                "attacks": [
+                    { "name": "Fire Spin", "cost": ["Fire"], "convertedEnergyCost": 4, "damage": "100", "text": "" }
+                ],
+                "set": {
+                    "id": "base1", "name": "Base", "series": "Base", "printedTotal": 102, "total": 102,
+                    "legalities": { "unlimited": "Legal" },
+                    "releaseDate": "1999/01/09", "updatedAt": "2020/08/14 09:35:00"
+                },
+                "number": "4",
+                "legalities": { "unlimited": "Legal" },
+                "images": { "small": "small.png", "large": "large.png" },
+                "tcgplayer": {
+                    "url": "https://prices.pokemontcg.io/tcgplayer/base1-4",
+                    "updatedAt": "2021/08/04",
+                    "prices": {
+                        "reverseHolofoil": { "low": 200.0, "mid": 300.0, "high": 500.0, "market": 320.0, "directLow": 250.0 }
+                    }
+                },
+                "cardmarket": {
+                    "url": "https://prices.pokemontcg.io/cardmarket/base1-4",
+                    "updatedAt": "2021/08/04",
+                    "prices": { "avg30": 310.0 }
+                }
+            },
+            {
+                "id": "swsh4-25",
+                "name": "Charmander",
+                "supertype": "Pokémon",
+                "hp": "70",
+                "attacks": [
+                    { "name": "Scratch", "cost": ["Colorless"], "convertedEnergyCost": 1, "damage": "10", "text": "" }
+                ],
+                "set": {
+                    "id": "swsh4", "name": "Vivid Voltage", "series": "Sword & Shield", "printedTotal": 185, "total": 203,
+                    "legalities": { "standard": "Legal", "unlimited": "Legal", "expanded": "Legal" },
+                    "releaseDate": "2020/11/13", "updatedAt": "2020/11/13 16:20:00"
+                },
+                "number": "25",
+                "legalities": { "standard": "Legal", "unlimited": "Legal", "expanded": "Legal" },
+                "regulationMark": "D",
+                "images": { "small": "small.png", "large": "large.png" },
+                "cardmarket": {
+                    "url": "https://prices.pokemontcg.io/cardmarket/swsh4-25",
+                    "updatedAt": "2021/08/04",
+                    "prices": { "avg30": 0.5 }
+                }
+            },
+            {
+                "id": "xy1-1",
+                "name": "Venusaur-EX",
+                "supertype": "Pokémon",
+                "hp": "180",
+                "attacks": [
+                    { "name": "Poison Powder", "cost": ["Grass"], "convertedEnergyCost": 3, "damage": "60", "text": "" },
+                    { "name": "Jungle Hammer", "cost": ["Grass"], "convertedEnergyCost": 4, "damage": "90", "text": "" }
+                ],
+                "set": {
+                    "id": "xy1", "name": "XY", "series": "XY", "printedTotal": 146, "total": 146,
+                    "legalities": { "unlimited": "Legal", "expanded": "Legal" },
+                    "releaseDate": "2014/02/05", "updatedAt": "2020/08/14 09:35:00"
+                },
+                "number": "1",
+                "legalities": { "unlimited": "Legal", "expanded": "Legal" },
+                "images": { "small": "small.png", "large": "large.png" }
+            }
+        ]"#,
+        )
+        .unwrap()
+    }
+
+    fn ids(builder: &PokemonQueryBuilder) -> Vec<String> {
+        let page: Page<Card> = evaluate_query(&cards(), builder).unwrap();
+
+        page.data.into_iter().map(|card| card.id).collect()
+    }
+
+    #[test]
+    fn matches_text_wildcard_and_exact_values() {
+        let text = PokemonQueryBuilder::new().add_name("charizard");
+        let words = PokemonQueryBuilder::new().add_filter(CardField::AttackName, "jungle hammer");
+        let wildcard = PokemonQueryBuilder::new().add_name(FilterValue::wildcard("char*"));
+        let exact = PokemonQueryBuilder::new().add_name_exact("CHARMANDER");
+        let partial_exact = PokemonQueryBuilder::new().add_name_exact("char");
+
+        assert_eq!(ids(&text), vec!["base1-4"]);
+        assert_eq!(ids(&words), vec!["xy1-1"]);
+        assert_eq!(ids(&wildcard), vec!["base1-4", "swsh4-25"]);
+        assert_eq!(ids(&exact), vec!["swsh4-25"]);
+        assert!(ids(&partial_exact).is_empty());
+    }
+
+    #[test]
+    fn matches_numeric_and_date_ranges() {
+        let inclusive = PokemonQueryBuilder::new().add_hp_range("70", "120", true);
+        let exclusive = PokemonQueryBuilder::new().add_hp_range("70", "*", false);
+        let dates = PokemonQueryBuilder::new().add_filter(
+            SetField::ReleaseDate,
+            FilterValue::range("2000/01/01", "*", true),
+        );
+
+        assert_eq!(ids(&inclusive), vec!["base1-4", "swsh4-25"]);
+        assert_eq!(ids(&exclusive), vec!["base1-4", "xy1-1"]);
+        assert_eq!(ids(&dates), vec!["swsh4-25", "xy1-1"]);
+    }
+
+    #[test]
+    fn negates_excluded_values() {
+        let builder = PokemonQueryBuilder::new()
+            .exclude_name("charizard")
+            .exclude_filter(CardField::AttackName, "scratch");
+
+        assert_eq!(ids(&builder), vec!["xy1-1"]);
+        assert_eq!(
+            ids(&PokemonQueryBuilder::new().with_query(Query::term("name", "venusaur").not())),
+            vec!["base1-4", "swsh4-25"]
+        );
+    }
+
+    #[test]
+    fn matches_nested_fields_with_api_names() {
+        let legal = PokemonQueryBuilder::new().add_filter(CardField::LegalitiesExpanded, "legal");
+        let regulation = PokemonQueryBuilder::new().add_filter(CardField::RegulationMark, "d");
+        let average = PokemonQueryBuilder::new().add_filter(
+            CardField::CardmarketPrice(CardmarketPriceKind::Avg30),
+            FilterValue::range("100", "*", true),
+        );
+        let direct_low = PokemonQueryBuilder::new().add_filter(
+            CardField::TcgplayerPrice(
+                TcgplayerVariant::ReverseHolofoil,
+                TcgplayerPriceKind::DirectLow,
+            ),
+            FilterValue::range("*", "*", true),
+        );
+
+        assert_eq!(ids(&legal), vec!["swsh4-25", "xy1-1"]);
+        assert_eq!(ids(&regulation), vec!["swsh4-25"]);
+        assert_eq!(ids(&average), vec!["base1-4"]);
+        assert_eq!(ids(&direct_low), vec!["base1-4"]);
+    }
+
+    #[test]
+    fn orders_missing_values_last() {
+        let ascending = PokemonQueryBuilder::new()
+            .add_ordering(CardField::RegulationMark.ascending())
+            .add_ordering(CardField::Hp.descending());
+        let descending = PokemonQueryBuilder::new()
+            .add_ordering(CardField::CardmarketPrice(CardmarketPriceKind::Avg30).descending());
+        let numeric = PokemonQueryBuilder::new().add_ordering(CardField::Hp.ascending());
+
+        assert_eq!(ids(&ascending), vec!["swsh4-25", "xy1-1", "base1-4"]);
+        assert_eq!(ids(&descending), vec!["base1-4", "swsh4-25", "xy1-1"]);
+        assert_eq!(ids(&numeric), vec!["swsh4-25", "base1-4", "xy1-1"]);
+    }
+
+    #[test]
+    fn pages_results() {
+        let builder = PokemonQueryBuilder::new()
+            .add_ordering(CardField::Id.ascending())
+            .with_page_size(2)
+            .with_page(2);
+
+        let page: Page<Card> = evaluate_query(&cards(), &builder).unwrap();
+
+        assert_eq!(page.page, 2);
+        assert_eq!(page.page_size, 2);
+        assert_eq!(page.count, 1);
+        assert_eq!(page.total_count, 3);
+        assert_eq!(page.data[0].id, "xy1-1");
+
+        let past_end: Page<Card> = evaluate_query(&cards(), &builder.with_page(3)).unwrap();
+        assert!(past_end.data.is_empty());
+        assert_eq!(past_end.total_count, 3);
+    }
+
+    #[test]
+    fn keeps_only_selected_fields() {
+        let builder = PokemonQueryBuilder::new()
+            .add_id_exact(&CardId(String::from("swsh4-25")))
+            .add_select(CardField::Name)
+            .add_select(CardField::RegulationMark)
+            .add_select(SetField::Series);
+
+        let page: Page<Value> = evaluate_query(&cards(), &builder).unwrap();
+
+        assert_eq!(
+            page.data,
+            vec![serde_json::json!({
+                "name": "Charmander",
+                "regulationMark": "D",
+                "set": { "series": "Sword & Shield" }
+            })]
+        );
+    }
+}
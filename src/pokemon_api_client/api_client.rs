@@ -1,22 +1,67 @@
-use std::{fs::File, io::Cursor};
+use std::{
+    collections::{HashMap, HashSet},
+    fs::File,
+    future::Future,
+    io::Cursor,
+    sync::Arc,
+    time::Duration,
+};
 
-use reqwest::Client;
+use futures::{stream, Stream, StreamExt};
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use tokio_util::sync::CancellationToken;
 
 use crate::{
-    builder::QueryBuilder,
-    resource::{card::Card, set::Set, ApiResource},
+    builder::{pokemon::PokemonQueryBuilder, set::SetQueryBuilder, QueryBuilder},
+    resource::{card::Card, set::Set, ApiResource, Identifiable},
 };
 
-use super::error::ApiError;
+use super::{
+    batch::{chunk_card_ids, BatchFailure, CardBatch, MAX_BATCH_CONCURRENCY},
+    cache::ResponseCache,
+    client_builder::PokemonApiClientBuilder,
+    disk_cache::{unix_now, DiskCache, DiskCacheEntry},
+    error::ApiError,
+    page::{AllPages, Page, PageFailure},
+    rate_limit::{RateLimiter, RemainingBudget},
+    retry::RetryPolicy,
+    stream::stream_pages,
+    transport::{ReqwestTransport, Transport, TransportRequest, TransportResponse},
+};
 
+/// The default base url of the official pokemon tcg api.
 pub static API_URL: &str = "https://api.pokemontcg.io/v2";
 pub static API_KEY_HEADER: &str = "X-Api-Key";
+pub static MAX_PAGE_SIZE: u8 = 250;
 
 /// Api client for querying the pokemon tcg api.
-pub struct PokemonApiClient {
-    api_key: String,
-    client: Client,
+/// Generic over the `Transport` sending the requests, which defaults to `reqwest`.
+pub struct PokemonApiClient<Tr = ReqwestTransport> {
+    pub(super) base_url: String,
+    pub(super) api_key: Option<String>,
+    pub(super) transport: Arc<Tr>,
+    pub(super) retry_policy: RetryPolicy,
+    pub(super) rate_limiter: Option<RateLimiter>,
+    pub(super) timeout: Option<Duration>,
+    pub(super) cancellation_token: Option<CancellationToken>,
+    pub(super) cache: Option<ResponseCache>,
+    pub(super) disk_cache: Option<DiskCache>,
+}
+
+impl<Tr> Clone for PokemonApiClient<Tr> {
+    fn clone(&self) -> Self {
+        PokemonApiClient {
+            base_url: self.base_url.clone(),
+            api_key: self.api_key.clone(),
+            transport: Arc::clone(&self.transport),
+            retry_policy: self.retry_policy.clone(),
+            rate_limiter: self.rate_limiter.clone(),
+            timeout: self.timeout,
+            cancellation_token: self.cancellation_token.clone(),
+            cache: self.cache.clone(),
+            disk_cache: self.disk_cache.clone(),
+        }
+    }
 }
 
 /// Container for api responses. Contains data of the generic type T.
@@ -26,9 +71,11 @@ pub struct ApiResponse<T> {
 }
 
 /// Wrapper for card ids.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct CardId(pub String);
 
 /// Wrapper for set ids.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct SetId(pub String);
 
 impl PokemonApiClient {
@@ -40,11 +87,119 @@ impl PokemonApiClient {
     #[must_use]
     pub fn new(api_key: &str) -> Self {
         PokemonApiClient {
-            api_key: api_key.to_owned(),
-            client: reqwest::Client::new(),
+            base_url: API_URL.to_owned(),
+            api_key: Some(api_key.to_owned()),
+            transport: Arc::new(ReqwestTransport::new()),
+            retry_policy: RetryPolicy::none(),
+            rate_limiter: None,
+            timeout: None,
+            cancellation_token: None,
+            cache: None,
+            disk_cache: None,
         }
     }
 
+    /// Creates a new `PokemonApiClientBuilder` for configuring the client.
+    #[must_use]
+    pub fn builder() -> PokemonApiClientBuilder {
+        PokemonApiClientBuilder::new()
+    }
+}
+
+impl<Tr: Transport> PokemonApiClient<Tr> {
+    /// Returns the remaining budget of every quota of the rate limiter, if one is configured.
+    #[must_use]
+    pub fn rate_limit_remaining(&self) -> Option<Vec<RemainingBudget>> {
+        self.rate_limiter.as_ref().map(RateLimiter::remaining)
+    }
+
+    /// Returns a copy of the client whose requests time out after the given duration.
+    /// Overrides the timeout of the client for all calls made through the copy.
+    ///
+    /// # Arguments
+    ///
+    /// * `timeout` - The maximum duration of a single request attempt.
+    #[must_use]
+    pub fn with_timeout(&self, timeout: Duration) -> Self {
+        let mut client = self.clone();
+        client.timeout = Some(timeout);
+        client
+    }
+
+    /// Returns a copy of the client whose calls are aborted with `ApiError::Cancelled`
+    /// as soon as the token is cancelled. Streams and multi-page calls of the copy stop as well.
+    ///
+    /// # Arguments
+    ///
+    /// * `token` - The token used to cancel the calls.
+    #[must_use]
+    pub fn with_cancellation_token(&self, token: CancellationToken) -> Self {
+        let mut client = self.clone();
+        client.cancellation_token = Some(token);
+        client
+    }
+
+    /// Returns the timeout of a single request attempt, if any.
+    #[must_use]
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    /// Returns the in memory response cache, if one is configured.
+    #[must_use]
+    pub fn cache(&self) -> Option<&ResponseCache> {
+        self.cache.as_ref()
+    }
+
+    /// Returns the persistent disk cache, if one is configured.
+    #[must_use]
+    pub fn disk_cache(&self) -> Option<&DiskCache> {
+        self.disk_cache.as_ref()
+    }
+
+    /// Removes the cached response of an url, e.g. one built by a `QueryBuilder`.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - The complete url of the request.
+    pub fn invalidate_cached_url(&self, url: &str) {
+        if let Some(cache) = &self.cache {
+            cache.invalidate(url);
+        }
+    }
+
+    /// Removes all cached responses of an api resource, including single lookups and queries.
+    pub fn invalidate_cached_resource<T: ApiResource>(&self) {
+        if let Some(cache) = &self.cache {
+            cache.invalidate_prefix(&T::url(&self.base_url));
+        }
+    }
+
+    /// Removes all cached responses.
+    pub fn clear_cache(&self) {
+        if let Some(cache) = &self.cache {
+            cache.clear();
+        }
+    }
+
+    /// Returns the transport sending the requests of this client.
+    #[must_use]
+    pub fn transport(&self) -> &Tr {
+        &self.transport
+    }
+
+    /// Returns the base url all api paths are resolved against.
+    #[must_use]
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// Returns the policy used for retrying failed requests.
+    #[must_use]
+    pub fn retry_policy(&self) -> &RetryPolicy {
+        &self.retry_policy
+    }
+
     /// Gets results from the api based on a resource path (url).
     ///
     /// # Arguments
@@ -54,19 +209,11 @@ impl PokemonApiClient {
     /// # Errors
     ///
     /// Will return `Err` if an error occures during either api querying or json parsing.
-    pub async fn get_resource<'a, T>(&self, resource_path: &str) -> Result<T, ApiError>
+    pub async fn get_resource<T>(&self, resource_path: &str) -> Result<T, ApiError>
     where
         T: DeserializeOwned,
     {
-        let res = self
-            .client
-            .get(resource_path)
-            .header(API_KEY_HEADER, &self.api_key)
-            .send()
-            .await?;
-
-        let json = res.text().await?;
-        let api_response: ApiResponse<T> = serde_json::from_str(&json)?;
+        let api_response: ApiResponse<T> = self.get_json(resource_path).await?;
 
         Ok(api_response.data)
     }
@@ -80,24 +227,279 @@ impl PokemonApiClient {
     /// # Errors
     ///
     /// Will return `Err` if an error occures during either api querying or json parsing.
-    pub async fn get_queryable_resources<'a, T, Q>(&self, query_builder: Q) -> Result<T, ApiError>
+    pub async fn get_queryable_resources<T, Q>(&self, query_builder: Q) -> Result<T, ApiError>
+    where
+        T: DeserializeOwned + ApiResource,
+        Q: QueryBuilder,
+    {
+        let query_url = query_builder.build(&T::url(&self.base_url));
+        let api_response: ApiResponse<T> = self.get_json(&query_url).await?;
+
+        Ok(api_response.data)
+    }
+
+    /// Gets a single page of results from the api based on a `QueryBuilder`.
+    /// Unlike `get_queryable_resources` the pagination metadata of the response is kept.
+    ///
+    /// # Arguments
+    ///
+    /// * `query_builder` - The query builder which creates the query parameters.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an error occures during either api querying or json parsing.
+    pub async fn get_queryable_resources_page<T, Q>(
+        &self,
+        query_builder: Q,
+    ) -> Result<Page<T>, ApiError>
     where
         T: DeserializeOwned + ApiResource,
         Q: QueryBuilder,
     {
-        let query_url = query_builder.build(&T::url());
+        let query_url = query_builder.build(&T::url(&self.base_url));
+
+        self.get_json(&query_url).await
+    }
+
+    /// Streams every item matching a `QueryBuilder`, fetching the following pages lazily
+    /// until the total count of the query is exhausted.
+    /// Starts at the page set on the query builder or at the first page.
+    ///
+    /// # Arguments
+    ///
+    /// * `query_builder` - The query builder which creates the query parameters.
+    /// * `prefetch` - Sets whether the next page is fetched in the background while the current one is consumed.
+    ///
+    /// # Errors
+    ///
+    /// The stream yields an `Err` and ends if an error occures during either api querying or json parsing.
+    pub fn stream_queryable_resources<T, Q>(
+        &self,
+        query_builder: Q,
+        prefetch: bool,
+    ) -> impl Stream<Item = Result<T, ApiError>>
+    where
+        T: DeserializeOwned + ApiResource + Send + 'static,
+        Q: QueryBuilder + Clone + Send + 'static,
+    {
+        stream_pages(self.clone(), query_builder, prefetch)
+    }
 
-        let res = self
-            .client
-            .get(query_url)
-            .header(API_KEY_HEADER, &self.api_key)
-            .send()
+    /// Gets every item matching a `QueryBuilder` by walking over all of its pages.
+    /// The first page is fetched alone to get the total count, the remaining pages are fetched
+    /// concurrently and merged in order. Items appearing on more than one page are only kept once.
+    /// Uses the biggest possible page size if the query builder has none set.
+    ///
+    /// # Arguments
+    ///
+    /// * `query_builder` - The query builder which creates the query parameters.
+    /// * `max_concurrency` - The maximum amount of pages fetched at the same time.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the first page cannot be fetched.
+    /// Failures of later pages are reported in the `failed_pages` of the result.
+    pub async fn get_all_queryable_resources<T, Q>(
+        &self,
+        query_builder: Q,
+        max_concurrency: usize,
+    ) -> Result<AllPages<T>, ApiError>
+    where
+        T: DeserializeOwned + ApiResource + Identifiable,
+        Q: QueryBuilder + Clone,
+    {
+        let query_builder = match query_builder.page_size() {
+            Some(_) => query_builder,
+            None => query_builder.with_page_size(MAX_PAGE_SIZE),
+        };
+
+        let first_page: Page<T> = self
+            .get_queryable_resources_page(query_builder.clone().with_page(1))
             .await?;
+        let total_pages = first_page.total_pages();
+        let total_count = first_page.total_count;
 
-        let json = res.text().await?;
-        let api_response: ApiResponse<T> = serde_json::from_str(&json)?;
+        let remaining_pages = stream::iter(2..=total_pages)
+            .map(|page| {
+                let query_builder = query_builder.clone().with_page(page);
+                async move { (page, self.get_queryable_resources_page(query_builder).await) }
+            })
+            .buffered(max_concurrency.max(1))
+            .collect::<Vec<(u32, Result<Page<T>, ApiError>)>>()
+            .await;
 
-        Ok(api_response.data)
+        if remaining_pages
+            .iter()
+            .any(|(_, result)| matches!(result, Err(ApiError::Cancelled)))
+        {
+            return Err(ApiError::Cancelled);
+        }
+
+        let mut seen_ids = HashSet::new();
+        let mut data = Vec::with_capacity(total_count as usize);
+        let mut failed_pages = Vec::new();
+
+        let mut pages = vec![first_page];
+        for (page, result) in remaining_pages {
+            match result {
+                Ok(fetched) => pages.push(fetched),
+                Err(error) => failed_pages.push(PageFailure { page, error }),
+            }
+        }
+
+        for item in pages.into_iter().flat_map(|page| page.data) {
+            if seen_ids.insert(item.id().to_owned()) {
+                data.push(item);
+            }
+        }
+
+        Ok(AllPages {
+            data,
+            total_count,
+            failed_pages,
+        })
+    }
+
+    async fn get_json<T>(&self, url: &str) -> Result<T, ApiError>
+    where
+        T: DeserializeOwned,
+    {
+        let body = self.get_body(url).await?;
+
+        Ok(serde_json::from_slice(&body)?)
+    }
+
+    async fn get_body(&self, url: &str) -> Result<Vec<u8>, ApiError> {
+        if let Some(body) = self.cache.as_ref().and_then(|cache| cache.get(url)) {
+            return Ok(body);
+        }
+
+        let body = match &self.disk_cache {
+            Some(disk_cache) => match disk_cache.load(url) {
+                Some(entry) if disk_cache.is_fresh(&entry) => entry.body.into_bytes(),
+                cached => self.fetch_revalidated(disk_cache, url, cached).await?,
+            },
+            None => {
+                self.with_retries(url, || self.send(url, true, &[]))
+                    .await?
+                    .body
+            }
+        };
+
+        if let Some(cache) = &self.cache {
+            let path = url.strip_prefix(self.base_url.as_str()).unwrap_or(url);
+            cache.insert(url, path, body.clone());
+        }
+
+        Ok(body)
+    }
+
+    async fn fetch_revalidated(
+        &self,
+        disk_cache: &DiskCache,
+        url: &str,
+        cached: Option<DiskCacheEntry>,
+    ) -> Result<Vec<u8>, ApiError> {
+        let mut validators = Vec::new();
+
+        if let Some(entry) = &cached {
+            if let Some(etag) = &entry.etag {
+                validators.push(("If-None-Match", etag.clone()));
+            }
+            if let Some(last_modified) = &entry.last_modified {
+                validators.push(("If-Modified-Since", last_modified.clone()));
+            }
+        }
+
+        let response = self
+            .with_retries(url, || self.send(url, true, &validators))
+            .await?;
+
+        let entry = match cached {
+            Some(mut entry) if response.status == 304 => {
+                entry.stored_at = unix_now();
+                entry
+            }
+            _ => DiskCacheEntry {
+                url: url.to_owned(),
+                etag: response.header("ETag").map(str::to_owned),
+                last_modified: response.header("Last-Modified").map(str::to_owned),
+                stored_at: unix_now(),
+                body: String::from_utf8_lossy(&response.body).into_owned(),
+            },
+        };
+
+        if let Err(err) = disk_cache.store(&entry) {
+            log::warn!("Could not store the response of {url} in the disk cache: {err}");
+        }
+
+        Ok(entry.body.into_bytes())
+    }
+
+    async fn send(
+        &self,
+        url: &str,
+        is_api_request: bool,
+        headers: &[(&str, String)],
+    ) -> Result<TransportResponse, ApiError> {
+        let mut request = TransportRequest::new(url);
+
+        for (name, value) in headers {
+            request = request.with_header(name, value);
+        }
+
+        if is_api_request {
+            if let Some(rate_limiter) = &self.rate_limiter {
+                rate_limiter.acquire().await;
+            }
+            if let Some(api_key) = &self.api_key {
+                request = request.with_header(API_KEY_HEADER, api_key);
+            }
+        }
+
+        let response = match self.timeout {
+            Some(timeout) => tokio::time::timeout(timeout, self.transport.send(request))
+                .await
+                .map_err(|_| ApiError::Timeout(timeout))??,
+            None => self.transport.send(request).await?,
+        };
+
+        check_status(response)
+    }
+
+    async fn with_retries<T, F, Fut>(&self, url: &str, mut request: F) -> Result<T, ApiError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, ApiError>>,
+    {
+        let retries = async {
+            let mut attempt = 1;
+
+            loop {
+                match request().await {
+                    Ok(result) => return Ok(result),
+                    Err(err) => match self.retry_policy.retry_delay(attempt, &err) {
+                        Some(delay) => {
+                            log::debug!(
+                                "Attempt {attempt} for {url} failed ({err}), retrying in {delay:?}"
+                            );
+                            tokio::time::sleep(delay).await;
+                            attempt += 1;
+                        }
+                        None => return Err(err),
+                    },
+                }
+            }
+        };
+
+        match &self.cancellation_token {
+            Some(token) => tokio::select! {
+                biased;
+                () = token.cancelled() => Err(ApiError::Cancelled),
+                result = retries => result,
+            },
+            None => retries.await,
+        }
     }
 
     /// Downloads a image from the api to the designated destination.
@@ -111,10 +513,12 @@ impl PokemonApiClient {
     ///
     /// Will return `Err` if the file cannot be downloaded or not be saved to the file system.
     pub async fn download_image(&self, url: &str, destination: &str) -> Result<(), ApiError> {
-        let response = reqwest::get(url).await?;
+        let response = self
+            .with_retries(url, || self.send(url, false, &[]))
+            .await?;
 
         let mut file = File::create(destination)?;
-        let mut content = Cursor::new(response.bytes().await?);
+        let mut content = Cursor::new(response.body);
         std::io::copy(&mut content, &mut file)?;
 
         Ok(())
@@ -130,22 +534,121 @@ impl PokemonApiClient {
     ///
     /// Will return `Err` if an error occures during either api querying or json parsing.
     pub async fn get_card(&self, id: CardId) -> Result<Card, ApiError> {
-        let card_url = format!("{API_URL}/cards/{}", id.0);
+        let card_url = format!("{}/cards/{}", self.base_url, id.0);
 
         self.get_resource(&card_url).await
     }
 
+    /// Gets many cards from the api based on their ids.
+    /// The ids are packed into as few queries as possible, which are fetched concurrently.
+    ///
+    /// # Arguments
+    ///
+    /// * `ids` - The ids of the cards to retrieve.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the lookup was cancelled.
+    /// Missing cards and failed queries are reported in the result.
+    pub async fn get_cards(&self, ids: &[CardId]) -> Result<CardBatch, ApiError> {
+        let mut unique_ids = HashSet::new();
+        let requested_ids = ids
+            .iter()
+            .filter(|id| unique_ids.insert(id.0.to_lowercase()))
+            .cloned()
+            .collect::<Vec<CardId>>();
+
+        let cards_url = format!("{}/cards", self.base_url);
+        let results = stream::iter(chunk_card_ids(&cards_url, &requested_ids))
+            .map(|(chunk_ids, query_builder)| async move {
+                let result = self
+                    .get_queryable_resources::<Vec<Card>, _>(query_builder)
+                    .await;
+                (chunk_ids, result)
+            })
+            .buffered(MAX_BATCH_CONCURRENCY)
+            .collect::<Vec<(Vec<CardId>, Result<Vec<Card>, ApiError>)>>()
+            .await;
+
+        if results
+            .iter()
+            .any(|(_, result)| matches!(result, Err(ApiError::Cancelled)))
+        {
+            return Err(ApiError::Cancelled);
+        }
+
+        let mut found = HashMap::new();
+        let mut failed = Vec::new();
+        let mut failed_ids = HashSet::new();
+
+        for (chunk_ids, result) in results {
+            match result {
+                Ok(cards) => {
+                    for card in cards {
+                        found.insert(card.id.to_lowercase(), card);
+                    }
+                }
+                Err(error) => {
+                    failed_ids.extend(chunk_ids.iter().map(|id| id.0.to_lowercase()));
+                    failed.push(BatchFailure {
+                        ids: chunk_ids,
+                        error,
+                    });
+                }
+            }
+        }
+
+        let mut cards = Vec::with_capacity(ids.len());
+        let mut not_found = Vec::new();
+
+        for id in ids {
+            let key = id.0.to_lowercase();
+
+            match found.get(&key) {
+                Some(card) => cards.push(card.clone()),
+                None if failed_ids.contains(&key) => {}
+                None if !not_found.contains(id) => not_found.push(id.clone()),
+                None => {}
+            }
+        }
+
+        Ok(CardBatch {
+            cards,
+            not_found,
+            failed,
+        })
+    }
+
     /// Gets a list of all cards from the api.
+    /// Only the first page of the api is returned, use `get_all_cards_exhaustive` to get every card.
     ///
     /// # Errors
     ///
     /// Will return `Err` if an error occures during either api querying or json parsing.
     pub async fn get_all_cards(&self) -> Result<Vec<Card>, ApiError> {
-        let cards_url = format!("{API_URL}/cards");
+        let cards_url = format!("{}/cards", self.base_url);
 
         self.get_resource(&cards_url).await
     }
 
+    /// Gets every card from the api by walking over all pages of the cards endpoint.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_concurrency` - The maximum amount of pages fetched at the same time.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the first page cannot be fetched.
+    /// Failures of later pages are reported in the `failed_pages` of the result.
+    pub async fn get_all_cards_exhaustive(
+        &self,
+        max_concurrency: usize,
+    ) -> Result<AllPages<Card>, ApiError> {
+        self.get_all_queryable_resources(PokemonQueryBuilder::new(), max_concurrency)
+            .await
+    }
+
     /// Gets a specific set from the api based on its id.
     ///
     /// # Arguments
@@ -156,29 +659,48 @@ impl PokemonApiClient {
     ///
     /// Will return `Err` if an error occures during either api querying or json parsing.
     pub async fn get_set(&self, id: SetId) -> Result<Set, ApiError> {
-        let sets_url = format!("{API_URL}/sets/{}", id.0);
+        let sets_url = format!("{}/sets/{}", self.base_url, id.0);
 
         self.get_resource(&sets_url).await
     }
 
     /// Gets a list of all sets from the api.
+    /// Only the first page of the api is returned, use `get_all_sets_exhaustive` to get every set.
     ///
     /// # Errors
     ///
     /// Will return `Err` if an error occures during either api querying or json parsing.
     pub async fn get_all_sets(&self) -> Result<Vec<Set>, ApiError> {
-        let set_url = format!("{API_URL}/sets");
+        let set_url = format!("{}/sets", self.base_url);
 
         self.get_resource(&set_url).await
     }
 
+    /// Gets every set from the api by walking over all pages of the sets endpoint.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_concurrency` - The maximum amount of pages fetched at the same time.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the first page cannot be fetched.
+    /// Failures of later pages are reported in the `failed_pages` of the result.
+    pub async fn get_all_sets_exhaustive(
+        &self,
+        max_concurrency: usize,
+    ) -> Result<AllPages<Set>, ApiError> {
+        self.get_all_queryable_resources(SetQueryBuilder::new(), max_concurrency)
+            .await
+    }
+
     /// Gets a list of all types from the api.
     ///
     /// # Errors
     ///
     /// Will return `Err` if an error occures during either api querying or json parsing.
     pub async fn get_all_types(&self) -> Result<Vec<String>, ApiError> {
-        let types_url = format!("{API_URL}/types");
+        let types_url = format!("{}/types", self.base_url);
 
         self.get_resource(&types_url).await
     }
@@ -189,7 +711,7 @@ impl PokemonApiClient {
     ///
     /// Will return `Err` if an error occures during either api querying or json parsing.
     pub async fn get_all_subtype(&self) -> Result<Vec<String>, ApiError> {
-        let types_url = format!("{API_URL}/subtypes");
+        let types_url = format!("{}/subtypes", self.base_url);
 
         self.get_resource(&types_url).await
     }
@@ -200,7 +722,7 @@ impl PokemonApiClient {
     ///
     /// Will return `Err` if an error occures during either api querying or json parsing.
     pub async fn get_all_supertypes(&self) -> Result<Vec<String>, ApiError> {
-        let types_url = format!("{API_URL}/supertypes");
+        let types_url = format!("{}/supertypes", self.base_url);
 
         self.get_resource(&types_url).await
     }
@@ -211,8 +733,434 @@ impl PokemonApiClient {
     ///
     /// Will return `Err` if an error occures during either api querying or json parsing.
     pub async fn get_all_rarities(&self) -> Result<Vec<String>, ApiError> {
-        let types_url = format!("{API_URL}/rarities");
+        let types_url = format!("{}/rarities", self.base_url);
 
         self.get_resource(&types_url).await
     }
 }
+
+/// Turns unsuccessful responses into the matching `ApiError`.
+/// Not modified responses are passed on, they only occur for conditional requests.
+fn check_status(response: TransportResponse) -> Result<TransportResponse, ApiError> {
+    if response.is_success() || response.status == 304 {
+        return Ok(response);
+    }
+
+    Err(ApiError::from_status(
+        response.status,
+        response.header("Retry-After"),
+        &String::from_utf8_lossy(&response.body),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use crate::pokemon_api_client::{
+        cache::CacheConfig,
+        error::ApiErrorKind,
+        transport::{MemoryTransport, TransportResponse},
+    };
+
+    use super::*;
+
+    static BASE_URL: &str = "https://api.test/v2";
+
+    #[derive(Deserialize, Debug)]
+    struct Item {
+        id: String,
+    }
+
+    impl ApiResource for Item {
+        fn path() -> String {
+            String::from("/items")
+        }
+    }
+
+    impl Identifiable for Item {
+        fn id(&self) -> &str {
+            &self.id
+        }
+    }
+
+    /// Answers the first requests with a server error and all following ones with the inner transport.
+    struct FlakyTransport {
+        inner: MemoryTransport,
+        failures: AtomicUsize,
+    }
+
+    impl Transport for FlakyTransport {
+        async fn send(&self, request: TransportRequest) -> Result<TransportResponse, ApiError> {
+            let remaining = self.failures.load(Ordering::SeqCst);
+
+            if remaining > 0 {
+                self.failures.store(remaining - 1, Ordering::SeqCst);
+                return Ok(TransportResponse::new(503, "unavailable"));
+            }
+
+            self.inner.send(request).await
+        }
+    }
+
+    /// Answers every request with the inner transport after a delay.
+    struct SlowTransport {
+        inner: MemoryTransport,
+        delay: Duration,
+    }
+
+    impl Transport for SlowTransport {
+        async fn send(&self, request: TransportRequest) -> Result<TransportResponse, ApiError> {
+            tokio::time::sleep(self.delay).await;
+
+            self.inner.send(request).await
+        }
+    }
+
+    fn client(transport: MemoryTransport) -> PokemonApiClient<MemoryTransport> {
+        PokemonApiClient::builder()
+            .with_base_url(BASE_URL)
+            .build_with_transport(transport)
+    }
+
+    fn retrying_client<Tr: Transport>(transport: Tr) -> PokemonApiClient<Tr> {
+        PokemonApiClient::builder()
+            .with_base_url(BASE_URL)
+            .with_retry_policy(
+                RetryPolicy::default()
+                    .with_base_delay(Duration::from_millis(1))
+                    .with_jitter(0.0),
+            )
+            .build_with_transport(transport)
+    }
+
+    fn card_json(id: &str) -> String {
+        format!(
+            r#"{{
+                "id": "{id}",
+                "name": "Card {id}",
+                "supertype": "Pokémon",
+                "set": {{
+                    "id": "base1",
+                    "name": "Base",
+                    "series": "Base",
+                    "printedTotal": 102,
+                    "total": 102,
+                    "legalities": {{ "unlimited": "Legal" }},
+                    "releaseDate": "1999/01/09",
+                    "updatedAt": "2020/08/14 09:35:00"
+                }},
+                "number": "1",
+                "legalities": {{ "unlimited": "Legal" }},
+                "images": {{ "small": "small.png", "large": "large.png" }}
+            }}"#
+        )
+    }
+
+    fn page_json(ids: &[&str], page: u32, page_size: u32, total_count: u32) -> String {
+        let data = ids
+            .iter()
+            .map(|id| format!(r#"{{"id":"{id}"}}"#))
+            .collect::<Vec<String>>()
+            .join(",");
+
+        format!(
+            r#"{{"data":[{data}],"page":{page},"pageSize":{page_size},"count":{},"totalCount":{total_count}}}"#,
+            ids.len()
+        )
+    }
+
+    #[tokio::test]
+    async fn maps_status_codes_to_errors() {
+        let cases = [
+            (400, ApiErrorKind::BadRequest),
+            (401, ApiErrorKind::Unauthorized),
+            (403, ApiErrorKind::Unauthorized),
+            (404, ApiErrorKind::NotFound),
+            (429, ApiErrorKind::RateLimited),
+            (503, ApiErrorKind::Server),
+            (418, ApiErrorKind::Status),
+        ];
+
+        for (status, kind) in cases {
+            let transport = MemoryTransport::new().with_response(
+                &format!("{BASE_URL}/types"),
+                TransportResponse::new(status, r#"{"error":{"message":"Failed","code":0}}"#),
+            );
+
+            let error = client(transport).get_all_types().await.unwrap_err();
+
+            assert_eq!(error.kind(), kind, "{status}");
+            assert_eq!(error.status(), Some(status), "{status}");
+            assert!(error.to_string().contains("Failed"), "{status}");
+        }
+    }
+
+    #[tokio::test]
+    async fn reads_retry_after_of_rate_limits() {
+        let transport = MemoryTransport::new().with_response(
+            &format!("{BASE_URL}/types"),
+            TransportResponse::new(429, "").with_header("Retry-After", "7"),
+        );
+
+        let error = client(transport).get_all_types().await.unwrap_err();
+
+        assert!(matches!(
+            error,
+            ApiError::RateLimited {
+                retry_after: Some(retry_after),
+                ..
+            } if retry_after == Duration::from_secs(7)
+        ));
+    }
+
+    #[tokio::test]
+    async fn retries_until_success() {
+        let transport = FlakyTransport {
+            inner: MemoryTransport::new()
+                .with_json(&format!("{BASE_URL}/types"), r#"{"data":["Fire"]}"#),
+            failures: AtomicUsize::new(2),
+        };
+        let client = retrying_client(transport);
+
+        assert_eq!(client.get_all_types().await.unwrap(), vec!["Fire"]);
+        assert_eq!(client.transport().failures.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn waits_for_retry_after_instead_of_backoff() {
+        let transport = MemoryTransport::new().with_response(
+            &format!("{BASE_URL}/types"),
+            TransportResponse::new(429, "").with_header("Retry-After", "0"),
+        );
+        let client = PokemonApiClient::builder()
+            .with_base_url(BASE_URL)
+            .with_retry_policy(RetryPolicy::default().with_base_delay(Duration::from_secs(3600)))
+            .build_with_transport(transport);
+
+        let result = tokio::time::timeout(Duration::from_secs(5), client.get_all_types()).await;
+
+        assert!(matches!(result, Ok(Err(ApiError::RateLimited { .. }))));
+        assert_eq!(client.transport().requests().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn does_not_retry_client_errors() {
+        let client = retrying_client(MemoryTransport::new());
+
+        let error = client.get_all_types().await.unwrap_err();
+
+        assert_eq!(error.kind(), ApiErrorKind::NotFound);
+        assert_eq!(client.transport().requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn reports_failed_pages() {
+        let query = PokemonQueryBuilder::new().with_page_size(2);
+        let url = |page: u32| query.clone().with_page(page).build(&Item::url(BASE_URL));
+
+        let transport = MemoryTransport::new()
+            .with_json(&url(1), &page_json(&["a", "b"], 1, 2, 5))
+            .with_json(&url(2), &page_json(&["b", "c"], 2, 2, 5))
+            .with_response(&url(3), TransportResponse::new(500, "broken"));
+
+        let all_pages = client(transport)
+            .get_all_queryable_resources::<Item, _>(query.clone(), 2)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            all_pages
+                .data
+                .iter()
+                .map(|item| item.id.as_str())
+                .collect::<Vec<&str>>(),
+            vec!["a", "b", "c"]
+        );
+        assert_eq!(all_pages.total_count, 5);
+        assert!(!all_pages.is_complete());
+        assert_eq!(all_pages.failed_pages.len(), 1);
+        assert_eq!(all_pages.failed_pages[0].page, 3);
+        assert_eq!(all_pages.failed_pages[0].error.kind(), ApiErrorKind::Server);
+    }
+
+    #[tokio::test]
+    async fn fails_if_first_page_fails() {
+        let query = PokemonQueryBuilder::new().with_page_size(2);
+
+        let result = client(MemoryTransport::new())
+            .get_all_queryable_resources::<Item, _>(query, 2)
+            .await;
+
+        assert!(matches!(result, Err(ApiError::NotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn keeps_page_metadata() {
+        let query = PokemonQueryBuilder::new().with_page(2).with_page_size(2);
+        let transport = MemoryTransport::new().with_json(
+            &query.build(&Item::url(BASE_URL)),
+            &page_json(&["c", "d"], 2, 2, 5),
+        );
+
+        let page = client(transport)
+            .get_queryable_resources_page::<Item, _>(query)
+            .await
+            .unwrap();
+
+        assert_eq!(page.total_pages(), 3);
+        assert!(page.has_next_page());
+        assert_eq!(page.data.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn gets_cards_in_requested_order() {
+        let ids = ["base1-3", "base1-1", "BASE1-1", "base1-2"]
+            .map(|id| CardId(String::from(id)))
+            .to_vec();
+        // Duplicates are dropped case insensitively before the ids are chunked.
+        let chunks = chunk_card_ids(
+            &format!("{BASE_URL}/cards"),
+            &[&ids[..2], &ids[3..]].concat(),
+        );
+        assert_eq!(chunks.len(), 1);
+
+        let transport = MemoryTransport::new().with_json(
+            &chunks[0].1.build(&format!("{BASE_URL}/cards")),
+            &format!(
+                r#"{{"data":[{},{}]}}"#,
+                card_json("base1-1"),
+                card_json("base1-3")
+            ),
+        );
+        let client = client(transport);
+
+        let batch = client.get_cards(&ids).await.unwrap();
+
+        assert_eq!(
+            batch
+                .cards
+                .iter()
+                .map(|card| card.id.as_str())
+                .collect::<Vec<&str>>(),
+            vec!["base1-3", "base1-1", "base1-1"]
+        );
+        assert_eq!(batch.not_found, vec![CardId(String::from("base1-2"))]);
+        assert!(batch.failed.is_empty());
+        assert_eq!(client.transport().requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn reports_failed_card_chunks() {
+        let ids = (0..300)
+            .map(|index| CardId(format!("sv{index:03}-{index}")))
+            .collect::<Vec<CardId>>();
+        let cards_url = format!("{BASE_URL}/cards");
+        let chunks = chunk_card_ids(&cards_url, &ids);
+        assert!(chunks.len() > 1);
+        assert!(chunks
+            .iter()
+            .all(|(_, query)| query.build(&cards_url).len() <= 2000));
+
+        let transport =
+            MemoryTransport::new().with_json(&chunks[0].1.build(&cards_url), r#"{"data":[]}"#);
+        let client = client(transport);
+
+        let batch = client.get_cards(&ids).await.unwrap();
+
+        assert!(batch.cards.is_empty());
+        assert_eq!(batch.not_found, chunks[0].0);
+        assert_eq!(batch.failed.len(), chunks.len() - 1);
+        assert!(batch
+            .failed
+            .iter()
+            .all(|failure| failure.error.kind() == ApiErrorKind::NotFound));
+        assert_eq!(client.transport().requests().len(), chunks.len());
+    }
+
+    #[tokio::test]
+    async fn serves_cached_responses_until_they_expire() {
+        let transport = MemoryTransport::new()
+            .with_json(&format!("{BASE_URL}/types"), r#"{"data":["Fire"]}"#)
+            .with_json(&format!("{BASE_URL}/rarities"), r#"{"data":["Rare"]}"#);
+        let cache = ResponseCache::new(
+            CacheConfig::new(Duration::from_millis(100)).with_path_ttl("/rarities", Duration::ZERO),
+        );
+        let client = PokemonApiClient::builder()
+            .with_base_url(BASE_URL)
+            .with_cache(cache)
+            .build_with_transport(transport);
+
+        client.get_all_types().await.unwrap();
+        client.get_all_types().await.unwrap();
+        assert_eq!(client.transport().requests().len(), 1);
+
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        client.get_all_types().await.unwrap();
+        assert_eq!(client.transport().requests().len(), 2);
+
+        client.get_all_rarities().await.unwrap();
+        client.get_all_rarities().await.unwrap();
+        assert_eq!(client.transport().requests().len(), 4);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn times_out_slow_responses() {
+        let transport = SlowTransport {
+            inner: MemoryTransport::new()
+                .with_json(&format!("{BASE_URL}/types"), r#"{"data":["Fire"]}"#),
+            delay: Duration::from_secs(10),
+        };
+        let client = PokemonApiClient::builder()
+            .with_base_url(BASE_URL)
+            .build_with_transport(transport);
+
+        let error = client
+            .with_timeout(Duration::from_secs(1))
+            .get_all_types()
+            .await
+            .unwrap_err();
+        assert!(matches!(error, ApiError::Timeout(timeout) if timeout == Duration::from_secs(1)));
+
+        let types = client
+            .with_timeout(Duration::from_secs(20))
+            .get_all_types()
+            .await
+            .unwrap();
+        assert_eq!(types, vec!["Fire"]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn cancels_during_retry_backoff() {
+        let transport = MemoryTransport::new().with_response(
+            &format!("{BASE_URL}/types"),
+            TransportResponse::new(503, "unavailable"),
+        );
+        let client = PokemonApiClient::builder()
+            .with_base_url(BASE_URL)
+            .with_retry_policy(
+                RetryPolicy::default()
+                    .with_base_delay(Duration::from_secs(60))
+                    .with_jitter(0.0),
+            )
+            .build_with_transport(transport);
+        let token = CancellationToken::new();
+
+        let cancel = token.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_secs(1)).await;
+            cancel.cancel();
+        });
+
+        let start = tokio::time::Instant::now();
+        let error = client
+            .with_cancellation_token(token)
+            .get_all_types()
+            .await
+            .unwrap_err();
+
+        assert!(matches!(error, ApiError::Cancelled));
+        assert_eq!(start.elapsed(), Duration::from_secs(1));
+        assert_eq!(client.transport().requests().len(), 1);
+    }
+}
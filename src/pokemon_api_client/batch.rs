@@ -0,0 +1,77 @@
+use crate::{
+    builder::{pokemon::PokemonQueryBuilder, QueryBuilder},
+    resource::card::Card,
+};
+
+use super::{
+    api_client::{CardId, MAX_PAGE_SIZE},
+    error::ApiError,
+};
+
+/// The maximum length of a query url of a batch, which is safely accepted by servers and proxies.
+pub static MAX_BATCH_URL_LENGTH: usize = 2000;
+/// The maximum amount of chunks of a batch fetched at the same time.
+pub static MAX_BATCH_CONCURRENCY: usize = 4;
+
+/// Result of looking up many cards by their ids.
+#[derive(Debug)]
+pub struct CardBatch {
+    /// The found cards in the order of the requested ids.
+    pub cards: Vec<Card>,
+    /// The requested ids which do not exist.
+    pub not_found: Vec<CardId>,
+    /// The chunks of ids which could not be fetched.
+    pub failed: Vec<BatchFailure>,
+}
+
+impl CardBatch {
+    /// Returns whether every requested card was found.
+    #[must_use]
+    pub fn is_complete(&self) -> bool {
+        self.not_found.is_empty() && self.failed.is_empty()
+    }
+}
+
+/// A chunk of ids which could not be fetched during a batch lookup.
+#[derive(Debug)]
+pub struct BatchFailure {
+    /// The ids of the chunk which failed.
+    pub ids: Vec<CardId>,
+    /// The error which occured while fetching the chunk.
+    pub error: ApiError,
+}
+
+/// Splits the ids into query builders with OR'ed id filters,
+/// each fitting into a single page and a url of at most `MAX_BATCH_URL_LENGTH`.
+pub(crate) fn chunk_card_ids(
+    cards_url: &str,
+    ids: &[CardId],
+) -> Vec<(Vec<CardId>, PokemonQueryBuilder)> {
+    let empty = || PokemonQueryBuilder::new().with_page_size(MAX_PAGE_SIZE);
+
+    let mut chunks = Vec::new();
+    let mut chunk_ids: Vec<CardId> = Vec::new();
+    let mut builder = empty();
+
+    for id in ids {
+        let extended = builder.clone().add_id(id);
+
+        if !chunk_ids.is_empty()
+            && (chunk_ids.len() >= usize::from(MAX_PAGE_SIZE)
+                || extended.build(cards_url).len() > MAX_BATCH_URL_LENGTH)
+        {
+            chunks.push((std::mem::take(&mut chunk_ids), builder));
+            builder = empty().add_id(id);
+        } else {
+            builder = extended;
+        }
+
+        chunk_ids.push(id.clone());
+    }
+
+    if !chunk_ids.is_empty() {
+        chunks.push((chunk_ids, builder));
+    }
+
+    chunks
+}
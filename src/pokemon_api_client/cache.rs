@@ -0,0 +1,215 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, PoisonError},
+    time::Duration,
+};
+
+use tokio::time::Instant;
+
+use crate::resource::ApiResource;
+
+/// Settings of the in memory response cache.
+/// The time to live of a response is chosen by the longest configured path its url starts with.
+#[derive(Clone, Debug)]
+pub struct CacheConfig {
+    default_ttl: Duration,
+    path_ttls: Vec<(String, Duration)>,
+}
+
+impl CacheConfig {
+    /// Creates a new cache config.
+    ///
+    /// # Arguments
+    ///
+    /// * `default_ttl` - The time to live of responses without a more specific setting.
+    #[must_use]
+    pub fn new(default_ttl: Duration) -> Self {
+        CacheConfig {
+            default_ttl,
+            path_ttls: Vec::new(),
+        }
+    }
+
+    /// Sets the time to live of responses whose path starts with the given path.
+    /// A time to live of zero disables caching for the path.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path relative to the base url, e.g. `/sets`.
+    /// * `ttl` - The time to live of the responses.
+    #[must_use]
+    pub fn with_path_ttl(mut self, path: &str, ttl: Duration) -> Self {
+        self.path_ttls.retain(|(existing, _)| existing != path);
+        self.path_ttls.push((path.to_owned(), ttl));
+        self
+    }
+
+    /// Sets the time to live of all responses of an api resource.
+    ///
+    /// # Arguments
+    ///
+    /// * `ttl` - The time to live of the responses.
+    #[must_use]
+    pub fn with_resource_ttl<T: ApiResource>(self, ttl: Duration) -> Self {
+        self.with_path_ttl(&T::path(), ttl)
+    }
+
+    /// Returns the time to live of responses for the given path.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path relative to the base url, including the query parameters.
+    #[must_use]
+    pub fn ttl_for(&self, path: &str) -> Duration {
+        self.path_ttls
+            .iter()
+            .filter(|(prefix, _)| path.starts_with(prefix.as_str()))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map_or(self.default_ttl, |(_, ttl)| *ttl)
+    }
+}
+
+#[derive(Debug)]
+struct CacheEntry {
+    body: Vec<u8>,
+    /// `None` if the time to live is too long to be represented, so the entry never expires.
+    expires_at: Option<Instant>,
+}
+
+impl CacheEntry {
+    fn is_expired(&self, now: Instant) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= now)
+    }
+}
+
+/// In memory cache of raw api responses keyed by their url.
+/// Clones share the same entries.
+#[derive(Clone, Debug)]
+pub struct ResponseCache {
+    config: CacheConfig,
+    entries: Arc<Mutex<HashMap<String, CacheEntry>>>,
+}
+
+impl ResponseCache {
+    /// Creates a new empty cache.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The settings deciding how long responses are kept.
+    #[must_use]
+    pub fn new(config: CacheConfig) -> Self {
+        ResponseCache {
+            config,
+            entries: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Returns the settings of the cache.
+    #[must_use]
+    pub fn config(&self) -> &CacheConfig {
+        &self.config
+    }
+
+    /// Returns the cached response body of an url if it has not expired yet.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - The complete url of the request.
+    #[must_use]
+    pub fn get(&self, url: &str) -> Option<Vec<u8>> {
+        let mut entries = self.entries();
+
+        match entries.get(url) {
+            Some(entry) if !entry.is_expired(Instant::now()) => Some(entry.body.clone()),
+            Some(_) => {
+                entries.remove(url);
+                None
+            }
+            None => None,
+        }
+    }
+
+    /// Stores a response body for an url.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - The complete url of the request.
+    /// * `path` - The path of the url relative to the base url, used to find the time to live.
+    /// * `body` - The raw body of the response.
+    pub fn insert(&self, url: &str, path: &str, body: Vec<u8>) {
+        let ttl = self.config.ttl_for(path);
+
+        if ttl.is_zero() {
+            return;
+        }
+
+        self.entries().insert(
+            url.to_owned(),
+            CacheEntry {
+                body,
+                expires_at: Instant::now().checked_add(ttl),
+            },
+        );
+    }
+
+    /// Removes the cached response of an url.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - The complete url of the request.
+    pub fn invalidate(&self, url: &str) {
+        self.entries().remove(url);
+    }
+
+    /// Removes the cached responses of all urls starting with the given prefix.
+    ///
+    /// # Arguments
+    ///
+    /// * `prefix` - The start of the urls to remove.
+    pub fn invalidate_prefix(&self, prefix: &str) {
+        self.entries().retain(|url, _| !url.starts_with(prefix));
+    }
+
+    /// Removes all expired responses.
+    pub fn purge_expired(&self) {
+        let now = Instant::now();
+
+        self.entries().retain(|_, entry| !entry.is_expired(now));
+    }
+
+    /// Removes all responses.
+    pub fn clear(&self) {
+        self.entries().clear();
+    }
+
+    /// Returns the amount of cached responses, including expired ones which were not purged yet.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.entries().len()
+    }
+
+    /// Returns whether the cache holds no responses.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.entries().is_empty()
+    }
+
+    fn entries(&self) -> std::sync::MutexGuard<'_, HashMap<String, CacheEntry>> {
+        self.entries.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_entries_with_unrepresentable_ttl() {
+        let cache = ResponseCache::new(CacheConfig::new(Duration::MAX));
+
+        cache.insert("https://api/cards", "/cards", b"[]".to_vec());
+        cache.purge_expired();
+
+        assert_eq!(cache.get("https://api/cards"), Some(b"[]".to_vec()));
+    }
+}
@@ -0,0 +1,198 @@
+use std::{sync::Arc, time::Duration};
+
+use reqwest::Client;
+
+use super::{
+    api_client::{PokemonApiClient, API_URL},
+    cache::ResponseCache,
+    disk_cache::DiskCache,
+    error::ApiError,
+    rate_limit::RateLimiter,
+    retry::RetryPolicy,
+    transport::{ReqwestTransport, Transport},
+};
+
+/// Builder for configuring and creating a `PokemonApiClient`.
+#[derive(Clone, Default)]
+pub struct PokemonApiClientBuilder {
+    base_url: Option<String>,
+    api_key: Option<String>,
+    timeout: Option<Duration>,
+    connect_timeout: Option<Duration>,
+    user_agent: Option<String>,
+    http_client: Option<Client>,
+    retry_policy: Option<RetryPolicy>,
+    rate_limiter: Option<RateLimiter>,
+    cache: Option<ResponseCache>,
+    disk_cache: Option<DiskCache>,
+}
+
+impl PokemonApiClientBuilder {
+    /// Creates a new instance of the `PokemonApiClientBuilder` with the default settings.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the base url all api paths are resolved against.
+    /// Defaults to the official pokemon tcg api.
+    ///
+    /// # Arguments
+    ///
+    /// * `base_url` - The base url of the api, e.g. `http://localhost:8080/v2`.
+    #[must_use]
+    pub fn with_base_url(mut self, base_url: &str) -> Self {
+        self.base_url = Some(base_url.trim_end_matches('/').to_owned());
+        self
+    }
+
+    /// Sets the api key which is sent with every request.
+    ///
+    /// # Arguments
+    ///
+    /// * `api_key` - The key to the pokemon tcg api.
+    #[must_use]
+    pub fn with_api_key(mut self, api_key: &str) -> Self {
+        self.api_key = Some(api_key.to_owned());
+        self
+    }
+
+    /// Sets the timeout for single request attempts, from connecting until the body is read.
+    /// Exceeding it fails the attempt with `ApiError::Timeout`, regardless of the transport.
+    ///
+    /// # Arguments
+    ///
+    /// * `timeout` - The maximum duration of a request attempt.
+    #[must_use]
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the timeout for connecting to the api.
+    ///
+    /// # Arguments
+    ///
+    /// * `timeout` - The maximum duration of the connect phase.
+    #[must_use]
+    pub fn with_connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the user agent which is sent with every request.
+    ///
+    /// # Arguments
+    ///
+    /// * `user_agent` - The value of the user agent header.
+    #[must_use]
+    pub fn with_user_agent(mut self, user_agent: &str) -> Self {
+        self.user_agent = Some(user_agent.to_owned());
+        self
+    }
+
+    /// Sets the underlying http client of the default transport.
+    /// Connect timeout and user agent of this builder are ignored, they have to be configured on the given client.
+    ///
+    /// # Arguments
+    ///
+    /// * `http_client` - The preconfigured `reqwest` client to use.
+    #[must_use]
+    pub fn with_http_client(mut self, http_client: Client) -> Self {
+        self.http_client = Some(http_client);
+        self
+    }
+
+    /// Sets the policy for retrying failed requests. By default requests are not retried.
+    ///
+    /// # Arguments
+    ///
+    /// * `retry_policy` - The policy deciding whether and when to retry.
+    #[must_use]
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = Some(retry_policy);
+        self
+    }
+
+    /// Sets the client side rate limiter. Requests wait for budget instead of exceeding a quota.
+    /// The limiter can be cloned to share one budget between several clients.
+    ///
+    /// # Arguments
+    ///
+    /// * `rate_limiter` - The rate limiter every api request has to pass.
+    #[must_use]
+    pub fn with_rate_limiter(mut self, rate_limiter: RateLimiter) -> Self {
+        self.rate_limiter = Some(rate_limiter);
+        self
+    }
+
+    /// Sets the in memory cache for api responses. Responses are cached by their url.
+    /// The cache can be cloned to share it between several clients.
+    ///
+    /// # Arguments
+    ///
+    /// * `cache` - The cache storing the responses.
+    #[must_use]
+    pub fn with_cache(mut self, cache: ResponseCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Sets the persistent disk cache for api responses, which survives restarts of the application.
+    /// Image downloads are not cached.
+    ///
+    /// # Arguments
+    ///
+    /// * `disk_cache` - The cache storing the responses on disk.
+    #[must_use]
+    pub fn with_disk_cache(mut self, disk_cache: DiskCache) -> Self {
+        self.disk_cache = Some(disk_cache);
+        self
+    }
+
+    /// Creates the `PokemonApiClient` from the settings of the builder, sending its requests with `reqwest`.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the underlying http client cannot be created.
+    pub fn build(mut self) -> Result<PokemonApiClient, ApiError> {
+        let client = match self.http_client.take() {
+            Some(client) => client,
+            None => {
+                let mut builder = Client::builder();
+
+                if let Some(timeout) = self.connect_timeout {
+                    builder = builder.connect_timeout(timeout);
+                }
+                if let Some(user_agent) = self.user_agent.take() {
+                    builder = builder.user_agent(user_agent);
+                }
+
+                builder.build()?
+            }
+        };
+
+        Ok(self.build_with_transport(ReqwestTransport::from_client(client)))
+    }
+
+    /// Creates the `PokemonApiClient` from the settings of the builder, sending its requests with a custom transport.
+    /// Connect timeout, user agent and http client of this builder are ignored.
+    ///
+    /// # Arguments
+    ///
+    /// * `transport` - The transport sending the requests of the client.
+    #[must_use]
+    pub fn build_with_transport<Tr: Transport>(self, transport: Tr) -> PokemonApiClient<Tr> {
+        PokemonApiClient {
+            base_url: self.base_url.unwrap_or_else(|| API_URL.to_owned()),
+            api_key: self.api_key,
+            transport: Arc::new(transport),
+            retry_policy: self.retry_policy.unwrap_or_else(RetryPolicy::none),
+            rate_limiter: self.rate_limiter,
+            timeout: self.timeout,
+            cancellation_token: None,
+            cache: self.cache,
+            disk_cache: self.disk_cache,
+        }
+    }
+}
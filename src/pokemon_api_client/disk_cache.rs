@@ -0,0 +1,250 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process,
+    sync::atomic::{AtomicU64, Ordering},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::error::ApiError;
+
+/// Counter making the names of temporary files unique within the process.
+static TEMP_FILE_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// A cached api response stored on disk, together with the data needed for revalidation.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DiskCacheEntry {
+    /// The complete url of the request.
+    pub url: String,
+    /// The `ETag` header of the response, if the api sent one.
+    pub etag: Option<String>,
+    /// The `Last-Modified` header of the response, if the api sent one.
+    pub last_modified: Option<String>,
+    /// The unix timestamp in seconds of when the response was fetched or last revalidated.
+    pub stored_at: u64,
+    /// The raw json body of the response.
+    pub body: String,
+}
+
+impl DiskCacheEntry {
+    /// Returns whether the response can be revalidated with a conditional request.
+    #[must_use]
+    pub fn can_revalidate(&self) -> bool {
+        self.etag.is_some() || self.last_modified.is_some()
+    }
+}
+
+/// Persistent cache of api responses with one file per url.
+/// Entries younger than the max age are used as they are, older ones are revalidated
+/// with `If-None-Match`/`If-Modified-Since` if possible and fetched again otherwise.
+#[derive(Clone, Debug)]
+pub struct DiskCache {
+    directory: PathBuf,
+    max_age: Duration,
+}
+
+impl DiskCache {
+    /// Creates a new disk cache, creating its directory if needed.
+    ///
+    /// # Arguments
+    ///
+    /// * `directory` - The directory the cache files are stored in.
+    /// * `max_age` - The age after which entries have to be revalidated.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the directory cannot be created.
+    pub fn new(directory: impl Into<PathBuf>, max_age: Duration) -> Result<Self, ApiError> {
+        let directory = directory.into();
+        fs::create_dir_all(&directory)?;
+
+        Ok(DiskCache { directory, max_age })
+    }
+
+    /// Returns the directory the cache files are stored in.
+    #[must_use]
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    /// Returns the age after which entries have to be revalidated.
+    #[must_use]
+    pub fn max_age(&self) -> Duration {
+        self.max_age
+    }
+
+    /// Loads the entry of an url. Unreadable or foreign entries are treated as missing.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - The complete url of the request.
+    #[must_use]
+    pub fn load(&self, url: &str) -> Option<DiskCacheEntry> {
+        let content = fs::read(self.file_path(url)).ok()?;
+        let entry: DiskCacheEntry = serde_json::from_slice(&content).ok()?;
+
+        (entry.url == url).then_some(entry)
+    }
+
+    /// Stores an entry, replacing the previous entry of its url.
+    ///
+    /// # Arguments
+    ///
+    /// * `entry` - The entry to store.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the entry cannot be written to the file system.
+    pub fn store(&self, entry: &DiskCacheEntry) -> Result<(), ApiError> {
+        write_file_atomically(&self.file_path(&entry.url), &serde_json::to_vec(entry)?)
+    }
+
+    /// Returns whether an entry is younger than the max age and can be used without revalidation.
+    ///
+    /// # Arguments
+    ///
+    /// * `entry` - The entry to check.
+    #[must_use]
+    pub fn is_fresh(&self, entry: &DiskCacheEntry) -> bool {
+        unix_now().saturating_sub(entry.stored_at) < self.max_age.as_secs()
+    }
+
+    /// Removes the entry of an url.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - The complete url of the request.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if an existing entry cannot be removed.
+    pub fn invalidate(&self, url: &str) -> Result<(), ApiError> {
+        match fs::remove_file(self.file_path(url)) {
+            Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(err.into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Removes all entries of the cache, including temporary files left behind by interrupted stores.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the cache directory cannot be read or an entry cannot be removed.
+    pub fn clear(&self) -> Result<(), ApiError> {
+        for dir_entry in fs::read_dir(&self.directory)? {
+            let path = dir_entry?.path();
+
+            let is_cache_file = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.ends_with(".json") || name.ends_with(".json.tmp"));
+
+            if is_cache_file {
+                fs::remove_file(path)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn file_path(&self, url: &str) -> PathBuf {
+        self.directory
+            .join(format!("{:016x}.json", fnv1a_hash(url)))
+    }
+}
+
+/// Writes the content to a temporary file next to the path and renames it over the path,
+/// so readers never see a half written file. Every write uses its own temporary file,
+/// so concurrent writes of a path cannot interleave, and removes it again if writing fails.
+pub(crate) fn write_file_atomically(path: &Path, content: &[u8]) -> Result<(), ApiError> {
+    let temp_path = path.with_extension(format!(
+        "{}-{}.json.tmp",
+        process::id(),
+        TEMP_FILE_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+
+    let result = fs::write(&temp_path, content).and_then(|()| fs::rename(&temp_path, path));
+
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+
+    Ok(result?)
+}
+
+/// Returns the current time as unix timestamp in seconds.
+pub(crate) fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs())
+}
+
+/// Hashes the url with FNV-1a, which unlike the std hasher is stable between runs and releases.
+fn fnv1a_hash(value: &str) -> u64 {
+    value.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_cache(name: &str) -> DiskCache {
+        let directory =
+            std::env::temp_dir().join(format!("pokemon-tcg-disk-cache-{name}-{}", process::id()));
+        let _ = fs::remove_dir_all(&directory);
+
+        DiskCache::new(directory, Duration::from_secs(60)).unwrap()
+    }
+
+    fn entry(url: &str, body: &str) -> DiskCacheEntry {
+        DiskCacheEntry {
+            url: url.to_owned(),
+            etag: None,
+            last_modified: None,
+            stored_at: unix_now(),
+            body: body.to_owned(),
+        }
+    }
+
+    #[test]
+    fn concurrent_stores_leave_a_complete_entry() {
+        let cache = temp_cache("concurrent");
+        let url = "https://api/cards";
+
+        std::thread::scope(|scope| {
+            for index in 0..8 {
+                let cache = &cache;
+                scope.spawn(move || {
+                    let body = index.to_string().repeat(10_000);
+                    for _ in 0..10 {
+                        cache.store(&entry(url, &body)).unwrap();
+                    }
+                });
+            }
+        });
+
+        let stored = cache.load(url).unwrap();
+        let first = stored.body.chars().next().unwrap();
+        assert!(stored.body.chars().all(|c| c == first));
+        assert_eq!(fs::read_dir(cache.directory()).unwrap().count(), 1);
+
+        fs::remove_dir_all(cache.directory()).unwrap();
+    }
+
+    #[test]
+    fn clear_removes_temporary_files() {
+        let cache = temp_cache("clear");
+        cache.store(&entry("https://api/sets", "[]")).unwrap();
+        fs::write(cache.directory().join("0123.1-2.json.tmp"), "{").unwrap();
+
+        cache.clear().unwrap();
+
+        assert_eq!(fs::read_dir(cache.directory()).unwrap().count(), 0);
+
+        fs::remove_dir_all(cache.directory()).unwrap();
+    }
+}
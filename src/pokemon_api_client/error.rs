@@ -1,12 +1,219 @@
-use std::{error::Error, fmt};
+use std::{
+    error::Error,
+    fmt,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use serde::Deserialize;
 
 /// Error for the uniting the different errors.
+/// Non exhaustive, as variants depend on the enabled features and new ones can be added,
+/// use `ApiError::kind` to react to errors without matching every variant.
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum ApiError {
     Reqwest(reqwest::Error),
     Deserialize(serde_json::Error),
     Io(std::io::Error),
     General(String),
+    /// The requested resource does not exist (404).
+    NotFound(String),
+    /// The rate limit of the api key is exceeded (429).
+    RateLimited {
+        retry_after: Option<Duration>,
+        message: String,
+    },
+    /// The api key is missing or invalid (401, 403).
+    Unauthorized {
+        status: u16,
+        message: String,
+    },
+    /// The api rejected the request, e.g. because of a malformed query (400).
+    BadRequest(String),
+    /// The api failed to process the request (5xx).
+    Server {
+        status: u16,
+        message: String,
+    },
+    /// The api answered with any other unexpected status code.
+    Status {
+        status: u16,
+        message: String,
+    },
+    /// The request did not finish within the configured timeout.
+    Timeout(Duration),
+    /// The operation was aborted through its cancellation token.
+    Cancelled,
+    /// The requested data is not part of the local snapshot.
+    NotInSnapshot(String),
+    /// The local sqlite storage failed.
+    #[cfg(feature = "sqlite")]
+    Sqlite(rusqlite::Error),
+}
+
+/// Kinds of `ApiError`, used to decide how to react to an error without matching its content.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ApiErrorKind {
+    Network,
+    Deserialize,
+    Io,
+    General,
+    NotFound,
+    RateLimited,
+    Unauthorized,
+    BadRequest,
+    Server,
+    Status,
+    Timeout,
+    Cancelled,
+    NotInSnapshot,
+    Storage,
+}
+
+/// Error body returned by the api for unsuccessful requests.
+#[derive(Deserialize, Debug)]
+struct ApiErrorResponse {
+    error: ApiErrorBody,
+}
+
+#[derive(Deserialize, Debug)]
+struct ApiErrorBody {
+    message: String,
+}
+
+impl ApiError {
+    /// Creates the matching error for an unsuccessful status code of the api.
+    ///
+    /// # Arguments
+    ///
+    /// * `status` - The status code of the response.
+    /// * `retry_after` - The value of the `Retry-After` header of the response, if any.
+    ///   Either an amount of seconds or an HTTP-date in the IMF-fixdate format, e.g. `Wed, 21 Oct 2015 07:28:00 GMT`.
+    ///   The obsolete RFC 850 and asctime date formats are not supported and ignored.
+    /// * `body` - The body of the response, which should contain the error message of the api.
+    #[must_use]
+    pub fn from_status(status: u16, retry_after: Option<&str>, body: &str) -> Self {
+        let message = match serde_json::from_str::<ApiErrorResponse>(body) {
+            Ok(response) => response.error.message,
+            Err(_) if body.trim().is_empty() => format!("Status code {status}"),
+            Err(_) => body.trim().to_owned(),
+        };
+
+        match status {
+            400 => ApiError::BadRequest(message),
+            401 | 403 => ApiError::Unauthorized { status, message },
+            404 => ApiError::NotFound(message),
+            429 => ApiError::RateLimited {
+                retry_after: retry_after
+                    .and_then(|value| parse_retry_after(value, SystemTime::now())),
+                message,
+            },
+            500..=599 => ApiError::Server { status, message },
+            _ => ApiError::Status { status, message },
+        }
+    }
+
+    /// Returns the kind of the error.
+    #[must_use]
+    pub fn kind(&self) -> ApiErrorKind {
+        match self {
+            ApiError::Reqwest(_) => ApiErrorKind::Network,
+            ApiError::Deserialize(_) => ApiErrorKind::Deserialize,
+            ApiError::Io(_) => ApiErrorKind::Io,
+            ApiError::General(_) => ApiErrorKind::General,
+            ApiError::NotFound(_) => ApiErrorKind::NotFound,
+            ApiError::RateLimited { .. } => ApiErrorKind::RateLimited,
+            ApiError::Unauthorized { .. } => ApiErrorKind::Unauthorized,
+            ApiError::BadRequest(_) => ApiErrorKind::BadRequest,
+            ApiError::Server { .. } => ApiErrorKind::Server,
+            ApiError::Status { .. } => ApiErrorKind::Status,
+            ApiError::Timeout(_) => ApiErrorKind::Timeout,
+            ApiError::Cancelled => ApiErrorKind::Cancelled,
+            ApiError::NotInSnapshot(_) => ApiErrorKind::NotInSnapshot,
+            #[cfg(feature = "sqlite")]
+            ApiError::Sqlite(_) => ApiErrorKind::Storage,
+        }
+    }
+
+    /// Returns the status code of the api response which caused this error, if any.
+    #[must_use]
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            ApiError::NotFound(_) => Some(404),
+            ApiError::RateLimited { .. } => Some(429),
+            ApiError::BadRequest(_) => Some(400),
+            ApiError::Unauthorized { status, .. }
+            | ApiError::Server { status, .. }
+            | ApiError::Status { status, .. } => Some(*status),
+            ApiError::Reqwest(err) => err.status().map(|status| status.as_u16()),
+            _ => None,
+        }
+    }
+}
+
+/// Names of the months in HTTP-dates.
+static MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+/// Parses the value of a `Retry-After` header into the delay from now.
+/// Dates in the past result in no delay.
+fn parse_retry_after(value: &str, now: SystemTime) -> Option<Duration> {
+    let value = value.trim();
+
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+
+    let date = parse_http_date(value)?;
+
+    Some(date.duration_since(now).unwrap_or(Duration::ZERO))
+}
+
+/// Parses an HTTP-date in the IMF-fixdate format, e.g. `Wed, 21 Oct 2015 07:28:00 GMT`.
+fn parse_http_date(value: &str) -> Option<SystemTime> {
+    let parts = value.split_whitespace().collect::<Vec<&str>>();
+    let [weekday, day, month, year, time, "GMT"] = parts.as_slice() else {
+        return None;
+    };
+
+    if !weekday.ends_with(',') {
+        return None;
+    }
+
+    let day = day
+        .parse::<u64>()
+        .ok()
+        .filter(|day| (1..=31).contains(day))?;
+    let month = MONTHS.iter().position(|name| name == month)? as u64 + 1;
+    let year = year
+        .parse::<u64>()
+        .ok()
+        .filter(|year| (1970..=9999).contains(year))?;
+
+    let time = time
+        .split(':')
+        .map(|part| part.parse::<u64>().ok())
+        .collect::<Option<Vec<u64>>>()?;
+    let [hours @ 0..=23, minutes @ 0..=59, seconds @ 0..=60] = time.as_slice() else {
+        return None;
+    };
+
+    let seconds = days_since_epoch(year, month, day) * 24 * 60 * 60
+        + hours * 60 * 60
+        + minutes * 60
+        + seconds;
+
+    Some(UNIX_EPOCH + Duration::from_secs(seconds))
+}
+
+/// Counts the days from 1970-01-01 to a date of the gregorian calendar between 1970 and 9999.
+fn days_since_epoch(year: u64, month: u64, day: u64) -> u64 {
+    // Years are counted from March, so the leap day is the last day of a year.
+    let year = if month <= 2 { year - 1 } else { year };
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+
+    year * 365 + year / 4 - year / 100 + year / 400 + day_of_year - 719_468
 }
 
 impl fmt::Display for ApiError {
@@ -16,6 +223,32 @@ impl fmt::Display for ApiError {
             ApiError::Deserialize(err) => write!(f, "serde_json error: {err}"),
             ApiError::Io(err) => write!(f, "IO error: {err}"),
             ApiError::General(s) => write!(f, "General error: {s}"),
+            ApiError::NotFound(s) => write!(f, "Not found: {s}"),
+            ApiError::RateLimited {
+                retry_after: Some(retry_after),
+                message,
+            } => write!(
+                f,
+                "Rate limited: {message}, retry after {}s",
+                retry_after.as_secs()
+            ),
+            ApiError::RateLimited {
+                retry_after: None,
+                message,
+            } => write!(f, "Rate limited: {message}"),
+            ApiError::Unauthorized { status, message } => {
+                write!(f, "Unauthorized {status}: {message}")
+            }
+            ApiError::BadRequest(s) => write!(f, "Bad request: {s}"),
+            ApiError::Server { status, message } => write!(f, "Server error {status}: {message}"),
+            ApiError::Status { status, message } => {
+                write!(f, "Unexpected status {status}: {message}")
+            }
+            ApiError::Timeout(timeout) => write!(f, "Request timed out after {timeout:?}"),
+            ApiError::Cancelled => write!(f, "Request was cancelled"),
+            ApiError::NotInSnapshot(s) => write!(f, "Not in snapshot: {s}"),
+            #[cfg(feature = "sqlite")]
+            ApiError::Sqlite(err) => write!(f, "Sqlite error: {err}"),
         }
     }
 }
@@ -26,7 +259,18 @@ impl Error for ApiError {
             ApiError::Reqwest(err) => Some(err),
             ApiError::Deserialize(err) => Some(err),
             ApiError::Io(err) => Some(err),
-            ApiError::General(_) => None,
+            #[cfg(feature = "sqlite")]
+            ApiError::Sqlite(err) => Some(err),
+            ApiError::General(_)
+            | ApiError::NotFound(_)
+            | ApiError::RateLimited { .. }
+            | ApiError::Unauthorized { .. }
+            | ApiError::BadRequest(_)
+            | ApiError::Server { .. }
+            | ApiError::Status { .. }
+            | ApiError::Timeout(_)
+            | ApiError::Cancelled
+            | ApiError::NotInSnapshot(_) => None,
         }
     }
 }
@@ -46,3 +290,80 @@ impl From<serde_json::error::Error> for ApiError {
         Self::Deserialize(e)
     }
 }
+#[cfg(feature = "sqlite")]
+impl From<rusqlite::Error> for ApiError {
+    fn from(e: rusqlite::Error) -> Self {
+        Self::Sqlite(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(seconds: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(seconds)
+    }
+
+    #[test]
+    fn parses_retry_after_seconds() {
+        assert_eq!(
+            parse_retry_after(" 120 ", at(0)),
+            Some(Duration::from_secs(120))
+        );
+    }
+
+    #[test]
+    fn parses_retry_after_dates() {
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT", at(1_445_412_480 - 90)),
+            Some(Duration::from_secs(90))
+        );
+        assert_eq!(
+            parse_retry_after("Thu, 01 Jan 1970 00:00:00 GMT", at(0)),
+            Some(Duration::ZERO)
+        );
+        assert_eq!(
+            parse_retry_after("Tue, 29 Feb 2000 23:59:59 GMT", at(951_782_400)),
+            Some(Duration::from_secs(24 * 60 * 60 - 1))
+        );
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT", at(1_445_412_480 + 60)),
+            Some(Duration::ZERO)
+        );
+    }
+
+    #[test]
+    fn ignores_unsupported_retry_after_values() {
+        let values = [
+            "",
+            "soon",
+            "-5",
+            "Wednesday, 21-Oct-15 07:28:00 GMT",
+            "Wed Oct 21 07:28:00 2015",
+            "Wed, 21 Oct 2015 07:28:00 CET",
+            "Wed, 32 Oct 2015 07:28:00 GMT",
+            "Wed, 21 Okt 2015 07:28:00 GMT",
+            "Wed, 21 Oct 2015 24:00:00 GMT",
+            "Wed, 21 Oct 2015 07:28 GMT",
+            "Wed, 21 Oct 18446744073709551615 07:28:00 GMT",
+        ];
+
+        for value in values {
+            assert_eq!(parse_retry_after(value, at(0)), None, "{value}");
+        }
+    }
+
+    #[test]
+    fn keeps_retry_after_of_rate_limits() {
+        let error = ApiError::from_status(429, Some("Thu, 01 Jan 1970 00:00:00 GMT"), "");
+
+        assert!(matches!(
+            error,
+            ApiError::RateLimited {
+                retry_after: Some(Duration::ZERO),
+                ..
+            }
+        ));
+    }
+}
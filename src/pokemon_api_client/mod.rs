@@ -1,3 +1,12 @@
 //! Contains the client implementation logic.
 pub mod api_client;
+pub mod batch;
+pub mod cache;
+pub mod client_builder;
+pub mod disk_cache;
 pub mod error;
+pub mod page;
+pub mod rate_limit;
+pub mod retry;
+mod stream;
+pub mod transport;
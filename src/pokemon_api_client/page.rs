@@ -0,0 +1,73 @@
+use serde::{Deserialize, Serialize};
+
+use super::error::ApiError;
+
+/// A single page of a list query, containing the items and the pagination metadata of the api.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Page<T> {
+    /// The items on this page.
+    pub data: Vec<T>,
+    /// The number of this page, starting at 1.
+    pub page: u32,
+    /// The maximum amount of items on a page.
+    #[serde(rename = "pageSize")]
+    pub page_size: u32,
+    /// The amount of items on this page.
+    pub count: u32,
+    /// The amount of items matching the query over all pages.
+    #[serde(rename = "totalCount")]
+    pub total_count: u32,
+}
+
+impl<T> Page<T> {
+    /// Returns the amount of pages needed to hold all items matching the query.
+    #[must_use]
+    pub fn total_pages(&self) -> u32 {
+        if self.page_size == 0 {
+            return 0;
+        }
+
+        self.total_count.div_ceil(self.page_size)
+    }
+
+    /// Returns whether there are more pages after this one.
+    #[must_use]
+    pub fn has_next_page(&self) -> bool {
+        self.page < self.total_pages()
+    }
+
+    /// Returns whether this is the last page of the query.
+    #[must_use]
+    pub fn is_last_page(&self) -> bool {
+        !self.has_next_page()
+    }
+}
+
+/// Result of fetching every page of a query.
+/// Pages which could not be fetched are reported instead of failing the whole query.
+#[derive(Debug)]
+pub struct AllPages<T> {
+    /// The items of all fetched pages in the order of the api, without duplicates.
+    pub data: Vec<T>,
+    /// The amount of items matching the query according to the first page.
+    pub total_count: u32,
+    /// The pages which could not be fetched.
+    pub failed_pages: Vec<PageFailure>,
+}
+
+impl<T> AllPages<T> {
+    /// Returns whether every page of the query was fetched successfully.
+    #[must_use]
+    pub fn is_complete(&self) -> bool {
+        self.failed_pages.is_empty()
+    }
+}
+
+/// A page which could not be fetched while walking over all pages of a query.
+#[derive(Debug)]
+pub struct PageFailure {
+    /// The number of the page which failed.
+    pub page: u32,
+    /// The error which occured while fetching the page.
+    pub error: ApiError,
+}
@@ -0,0 +1,252 @@
+use std::{
+    sync::{Arc, Mutex, PoisonError},
+    time::Duration,
+};
+
+use tokio::time::Instant;
+
+/// Amount of requests allowed within a period of time.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Quota {
+    pub requests: u32,
+    pub period: Duration,
+}
+
+impl Quota {
+    /// Creates a new quota.
+    ///
+    /// # Arguments
+    ///
+    /// * `requests` - The amount of requests allowed within the period.
+    /// * `period` - The period in which the budget refills completely.
+    #[must_use]
+    pub fn new(requests: u32, period: Duration) -> Self {
+        Quota { requests, period }
+    }
+
+    /// Creates a quota of requests per minute.
+    #[must_use]
+    pub fn per_minute(requests: u32) -> Self {
+        Self::new(requests, Duration::from_secs(60))
+    }
+
+    /// Creates a quota of requests per hour.
+    #[must_use]
+    pub fn per_hour(requests: u32) -> Self {
+        Self::new(requests, Duration::from_secs(60 * 60))
+    }
+
+    /// Creates a quota of requests per day.
+    #[must_use]
+    pub fn per_day(requests: u32) -> Self {
+        Self::new(requests, Duration::from_secs(24 * 60 * 60))
+    }
+}
+
+/// Remaining budget of a quota of the rate limiter.
+#[derive(Clone, Copy, Debug)]
+pub struct RemainingBudget {
+    pub quota: Quota,
+    pub remaining: u32,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    quota: Quota,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl Bucket {
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        let refill_rate = f64::from(self.quota.requests) / self.quota.period.as_secs_f64();
+
+        self.tokens = (self.tokens + elapsed * refill_rate).min(f64::from(self.quota.requests));
+        self.last_refill = now;
+    }
+
+    fn time_until_token(&self) -> Duration {
+        let missing = 1.0 - self.tokens;
+
+        if missing <= 0.0 {
+            return Duration::ZERO;
+        }
+
+        self.quota
+            .period
+            .mul_f64(missing / f64::from(self.quota.requests))
+    }
+}
+
+/// Client side token bucket rate limiter with one bucket per quota.
+/// Clones share the same budget, so a limiter can be used by many tasks and clients at once.
+/// Requests wait until every quota has budget left instead of failing.
+#[derive(Clone, Debug)]
+pub struct RateLimiter {
+    buckets: Arc<Mutex<Vec<Bucket>>>,
+}
+
+impl RateLimiter {
+    /// Creates a new rate limiter, starting with the full budget of every quota.
+    ///
+    /// # Arguments
+    ///
+    /// * `quotas` - The quotas which all have to be kept.
+    #[must_use]
+    pub fn new(quotas: &[Quota]) -> Self {
+        let now = Instant::now();
+        let buckets = quotas
+            .iter()
+            .filter(|quota| quota.requests > 0 && !quota.period.is_zero())
+            .map(|quota| Bucket {
+                quota: *quota,
+                tokens: f64::from(quota.requests),
+                last_refill: now,
+            })
+            .collect();
+
+        RateLimiter {
+            buckets: Arc::new(Mutex::new(buckets)),
+        }
+    }
+
+    /// Waits until every quota has budget left and takes one request from each of them.
+    pub async fn acquire(&self) {
+        loop {
+            let wait = {
+                let mut buckets = self.buckets.lock().unwrap_or_else(PoisonError::into_inner);
+                let now = Instant::now();
+
+                for bucket in buckets.iter_mut() {
+                    bucket.refill(now);
+                }
+
+                let wait = buckets
+                    .iter()
+                    .map(Bucket::time_until_token)
+                    .max()
+                    .unwrap_or(Duration::ZERO);
+
+                if wait.is_zero() {
+                    for bucket in buckets.iter_mut() {
+                        bucket.tokens -= 1.0;
+                    }
+
+                    return;
+                }
+
+                wait
+            };
+
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Returns the remaining budget of every quota.
+    #[must_use]
+    pub fn remaining(&self) -> Vec<RemainingBudget> {
+        let mut buckets = self.buckets.lock().unwrap_or_else(PoisonError::into_inner);
+        let now = Instant::now();
+
+        buckets
+            .iter_mut()
+            .map(|bucket| {
+                bucket.refill(now);
+
+                RemainingBudget {
+                    quota: bucket.quota,
+                    // The bucket never holds more tokens than the requests of its quota.
+                    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+                    remaining: bucket.tokens.floor() as u32,
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Allows the waited time to be slightly longer, as the refill is computed with floats.
+    fn assert_waited(waited: Duration, expected: Duration) {
+        assert!(
+            waited >= expected && waited < expected + Duration::from_millis(10),
+            "expected {expected:?}, waited {waited:?}"
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn allows_a_burst_of_the_whole_budget() {
+        let limiter = RateLimiter::new(&[Quota::new(3, Duration::from_secs(3))]);
+        let start = Instant::now();
+
+        for _ in 0..3 {
+            limiter.acquire().await;
+        }
+
+        assert_eq!(start.elapsed(), Duration::ZERO);
+        assert_eq!(limiter.remaining()[0].remaining, 0);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn refills_over_the_period() {
+        let limiter = RateLimiter::new(&[Quota::new(3, Duration::from_secs(3))]);
+        for _ in 0..3 {
+            limiter.acquire().await;
+        }
+
+        let start = Instant::now();
+        limiter.acquire().await;
+        assert_waited(start.elapsed(), Duration::from_secs(1));
+
+        tokio::time::sleep(Duration::from_secs(2)).await;
+        assert_eq!(limiter.remaining()[0].remaining, 2);
+
+        tokio::time::sleep(Duration::from_secs(60)).await;
+        assert_eq!(limiter.remaining()[0].remaining, 3);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn waits_for_the_strictest_quota() {
+        let limiter = RateLimiter::new(&[
+            Quota::new(10, Duration::from_secs(1)),
+            Quota::new(2, Duration::from_secs(10)),
+        ]);
+        let start = Instant::now();
+
+        for _ in 0..3 {
+            limiter.acquire().await;
+        }
+
+        assert_waited(start.elapsed(), Duration::from_secs(5));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn spreads_concurrent_waiters_over_the_refills() {
+        let limiter = RateLimiter::new(&[Quota::new(2, Duration::from_secs(2))]);
+        let start = Instant::now();
+
+        let waiters = (0..6)
+            .map(|_| {
+                let limiter = limiter.clone();
+
+                tokio::spawn(async move {
+                    limiter.acquire().await;
+                    start.elapsed()
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let mut waited = Vec::new();
+        for waiter in waiters {
+            waited.push(waiter.await.unwrap());
+        }
+        waited.sort();
+
+        for (waited, expected) in waited.into_iter().zip([0, 0, 1, 2, 3, 4]) {
+            assert_waited(waited, Duration::from_secs(expected));
+        }
+    }
+}
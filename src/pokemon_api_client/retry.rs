@@ -0,0 +1,220 @@
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    time::Duration,
+};
+
+use super::error::{ApiError, ApiErrorKind};
+
+/// Policy deciding whether and when failed requests are retried.
+/// The delay between attempts grows exponentially from the base delay up to the maximum delay.
+#[derive(Clone, Debug)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    base_delay: Duration,
+    max_delay: Duration,
+    jitter: f64,
+    respect_retry_after: bool,
+    retryable: Vec<ApiErrorKind>,
+}
+
+impl Default for RetryPolicy {
+    /// Retries network errors, timeouts, rate limits and server errors up to three attempts.
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+            jitter: 0.5,
+            respect_retry_after: true,
+            retryable: vec![
+                ApiErrorKind::Network,
+                ApiErrorKind::Timeout,
+                ApiErrorKind::RateLimited,
+                ApiErrorKind::Server,
+            ],
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Creates a policy which never retries failed requests.
+    #[must_use]
+    pub fn none() -> Self {
+        RetryPolicy {
+            max_attempts: 1,
+            ..Self::default()
+        }
+    }
+
+    /// Sets the maximum amount of attempts, including the first one.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_attempts` - The maximum amount of attempts of a request.
+    #[must_use]
+    pub fn with_max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// Sets the delay before the first retry, which doubles with every following retry.
+    ///
+    /// # Arguments
+    ///
+    /// * `base_delay` - The delay before the first retry.
+    #[must_use]
+    pub fn with_base_delay(mut self, base_delay: Duration) -> Self {
+        self.base_delay = base_delay;
+        self
+    }
+
+    /// Sets the upper bound of the exponentially growing delay and of the `Retry-After` delay of the api.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_delay` - The maximum delay between two attempts.
+    #[must_use]
+    pub fn with_max_delay(mut self, max_delay: Duration) -> Self {
+        self.max_delay = max_delay;
+        self
+    }
+
+    /// Sets the share of the delay which is randomized to spread out retries of concurrent requests.
+    ///
+    /// # Arguments
+    ///
+    /// * `jitter` - The randomized share between 0.0 (none) and 1.0 (the whole delay).
+    #[must_use]
+    pub fn with_jitter(mut self, jitter: f64) -> Self {
+        self.jitter = jitter.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Sets whether the `Retry-After` header of rate limited responses is used as delay.
+    /// The delay is capped at the maximum delay, so a misbehaving server cannot stall requests indefinitely.
+    ///
+    /// # Arguments
+    ///
+    /// * `respect_retry_after` - Whether to wait as long as the api asks for.
+    #[must_use]
+    pub fn with_retry_after(mut self, respect_retry_after: bool) -> Self {
+        self.respect_retry_after = respect_retry_after;
+        self
+    }
+
+    /// Sets the kinds of errors which are retried.
+    ///
+    /// # Arguments
+    ///
+    /// * `retryable` - The kinds of errors to retry.
+    #[must_use]
+    pub fn with_retryable(mut self, retryable: &[ApiErrorKind]) -> Self {
+        self.retryable = retryable.to_vec();
+        self
+    }
+
+    /// Returns the maximum amount of attempts, including the first one.
+    #[must_use]
+    pub fn max_attempts(&self) -> u32 {
+        self.max_attempts
+    }
+
+    /// Returns the delay before the next attempt, or `None` if the request should not be retried.
+    ///
+    /// # Arguments
+    ///
+    /// * `attempt` - The number of the attempt which failed, starting at 1.
+    /// * `error` - The error of the failed attempt.
+    #[must_use]
+    pub fn retry_delay(&self, attempt: u32, error: &ApiError) -> Option<Duration> {
+        if attempt >= self.max_attempts || !self.retryable.contains(&error.kind()) {
+            return None;
+        }
+
+        if let ApiError::RateLimited {
+            retry_after: Some(retry_after),
+            ..
+        } = error
+        {
+            if self.respect_retry_after {
+                return Some((*retry_after).min(self.max_delay));
+            }
+        }
+
+        let factor = 2u32.saturating_pow(attempt - 1);
+        let delay = self.base_delay.saturating_mul(factor).min(self.max_delay);
+        let jitter = delay.mul_f64(self.jitter * random_fraction());
+
+        Some(delay - jitter)
+    }
+}
+
+/// Returns a random number between 0.0 and 1.0.
+fn random_fraction() -> f64 {
+    let random = RandomState::new().build_hasher().finish();
+
+    // Only the upper 53 bits fit into the mantissa of a f64.
+    (random >> 11) as f64 / (1u64 << 53) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rate_limited(retry_after: Duration) -> ApiError {
+        ApiError::RateLimited {
+            retry_after: Some(retry_after),
+            message: String::from("Too many requests"),
+        }
+    }
+
+    #[test]
+    fn caps_retry_after_at_max_delay() {
+        let policy = RetryPolicy::default().with_max_delay(Duration::from_secs(10));
+
+        assert_eq!(
+            policy.retry_delay(1, &rate_limited(Duration::from_secs(3))),
+            Some(Duration::from_secs(3))
+        );
+        assert_eq!(
+            policy.retry_delay(1, &rate_limited(Duration::from_secs(24 * 60 * 60))),
+            Some(Duration::from_secs(10))
+        );
+    }
+
+    #[test]
+    fn grows_backoff_up_to_max_delay() {
+        let policy = RetryPolicy::default()
+            .with_max_attempts(10)
+            .with_base_delay(Duration::from_secs(1))
+            .with_max_delay(Duration::from_secs(5))
+            .with_jitter(0.0)
+            .with_retry_after(false);
+        let error = rate_limited(Duration::from_secs(60));
+
+        let delays = (1..=5)
+            .map(|attempt| policy.retry_delay(attempt, &error))
+            .collect::<Vec<Option<Duration>>>();
+
+        assert_eq!(
+            delays,
+            [1, 2, 4, 5, 5].map(|seconds| Some(Duration::from_secs(seconds)))
+        );
+    }
+
+    #[test]
+    fn stops_after_max_attempts_and_for_other_errors() {
+        let policy = RetryPolicy::default();
+
+        assert_eq!(policy.retry_delay(3, &ApiError::Cancelled), None);
+        assert_eq!(
+            policy.retry_delay(3, &rate_limited(Duration::from_secs(1))),
+            None
+        );
+        assert_eq!(
+            policy.retry_delay(1, &ApiError::NotFound(String::from("Not Found"))),
+            None
+        );
+    }
+}
@@ -0,0 +1,229 @@
+use std::collections::VecDeque;
+
+use futures::{stream, Stream};
+use serde::de::DeserializeOwned;
+use tokio::task::JoinHandle;
+
+use crate::{builder::QueryBuilder, resource::ApiResource};
+
+use super::{api_client::PokemonApiClient, error::ApiError, page::Page, transport::Transport};
+
+type PageHandle<T> = JoinHandle<Result<Page<T>, ApiError>>;
+
+/// State of a stream walking over all pages of a query.
+struct PageStreamState<T, Q, Tr> {
+    client: PokemonApiClient<Tr>,
+    query_builder: Q,
+    next_page: Option<u32>,
+    buffer: VecDeque<T>,
+    prefetched: Option<PageHandle<T>>,
+    prefetch: bool,
+}
+
+impl<T, Q, Tr> Drop for PageStreamState<T, Q, Tr> {
+    fn drop(&mut self) {
+        if let Some(handle) = self.prefetched.take() {
+            handle.abort();
+        }
+    }
+}
+
+impl<T, Q, Tr> PageStreamState<T, Q, Tr>
+where
+    T: DeserializeOwned + ApiResource + Send + 'static,
+    Q: QueryBuilder + Clone + Send + 'static,
+    Tr: Transport,
+{
+    fn spawn_fetch(&self, page: u32) -> PageHandle<T> {
+        let client = self.client.clone();
+        let query_builder = self.query_builder.clone().with_page(page);
+
+        tokio::spawn(async move { client.get_queryable_resources_page(query_builder).await })
+    }
+
+    async fn fetch(&mut self, page: u32) -> Result<Page<T>, ApiError> {
+        match self.prefetched.take() {
+            Some(handle) => handle
+                .await
+                .map_err(|err| ApiError::General(err.to_string()))?,
+            None => {
+                let query_builder = self.query_builder.clone().with_page(page);
+                self.client
+                    .get_queryable_resources_page(query_builder)
+                    .await
+            }
+        }
+    }
+}
+
+/// Creates a stream over every item matching the query, fetching one page after another.
+pub(crate) fn stream_pages<T, Q, Tr>(
+    client: PokemonApiClient<Tr>,
+    query_builder: Q,
+    prefetch: bool,
+) -> impl Stream<Item = Result<T, ApiError>>
+where
+    T: DeserializeOwned + ApiResource + Send + 'static,
+    Q: QueryBuilder + Clone + Send + 'static,
+    Tr: Transport,
+{
+    let state = PageStreamState {
+        client,
+        next_page: Some(query_builder.page().unwrap_or(1)),
+        query_builder,
+        buffer: VecDeque::new(),
+        prefetched: None,
+        prefetch,
+    };
+
+    stream::unfold(state, |mut state| async move {
+        loop {
+            if let Some(item) = state.buffer.pop_front() {
+                return Some((Ok(item), state));
+            }
+
+            let page_number = state.next_page?;
+
+            match state.fetch(page_number).await {
+                Ok(page) => {
+                    state.next_page = if page.has_next_page() && !page.data.is_empty() {
+                        Some(page.page + 1)
+                    } else {
+                        None
+                    };
+
+                    if let (Some(next_page), true) = (state.next_page, state.prefetch) {
+                        state.prefetched = Some(state.spawn_fetch(next_page));
+                    }
+
+                    state.buffer.extend(page.data);
+                }
+                Err(err) => {
+                    state.next_page = None;
+                    return Some((Err(err), state));
+                }
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::StreamExt;
+    use serde::Deserialize;
+
+    use super::*;
+    use crate::{
+        builder::set::SetQueryBuilder,
+        pokemon_api_client::{
+            error::ApiErrorKind,
+            transport::{MemoryTransport, TransportResponse},
+        },
+    };
+
+    static BASE_URL: &str = "https://api.test/v2";
+
+    #[derive(Deserialize, Debug)]
+    struct Item {
+        id: String,
+    }
+
+    impl ApiResource for Item {
+        fn path() -> String {
+            String::from("/items")
+        }
+    }
+
+    fn page_url(page: u32) -> String {
+        SetQueryBuilder::new()
+            .with_page_size(2)
+            .with_page(page)
+            .build(&Item::url(BASE_URL))
+    }
+
+    fn insert_page(transport: &MemoryTransport, page: u32, ids: &[&str], total_count: u32) {
+        let data = ids
+            .iter()
+            .map(|id| format!(r#"{{"id":"{id}"}}"#))
+            .collect::<Vec<String>>()
+            .join(",");
+
+        transport.insert(
+            &page_url(page),
+            TransportResponse::new(
+                200,
+                format!(
+                    r#"{{"data":[{data}],"page":{page},"pageSize":2,"count":{},"totalCount":{total_count}}}"#,
+                    ids.len()
+                ),
+            ),
+        );
+    }
+
+    fn client(transport: MemoryTransport) -> PokemonApiClient<MemoryTransport> {
+        PokemonApiClient::builder()
+            .with_base_url(BASE_URL)
+            .build_with_transport(transport)
+    }
+
+    async fn collect(transport: MemoryTransport, prefetch: bool) -> Vec<Result<Item, ApiError>> {
+        stream_pages(
+            client(transport),
+            SetQueryBuilder::new().with_page_size(2),
+            prefetch,
+        )
+        .collect()
+        .await
+    }
+
+    #[tokio::test]
+    async fn streams_every_page_until_total_count() {
+        for prefetch in [false, true] {
+            let transport = MemoryTransport::new();
+            insert_page(&transport, 1, &["1", "2"], 5);
+            insert_page(&transport, 2, &["3", "4"], 5);
+            insert_page(&transport, 3, &["5"], 5);
+            insert_page(&transport, 4, &["6"], 5);
+
+            let ids = collect(transport.clone(), prefetch)
+                .await
+                .into_iter()
+                .map(|item| item.unwrap().id)
+                .collect::<Vec<String>>();
+
+            assert_eq!(ids, vec!["1", "2", "3", "4", "5"], "prefetch: {prefetch}");
+            assert_eq!(
+                transport
+                    .requests()
+                    .iter()
+                    .map(|request| request.url.clone())
+                    .collect::<Vec<String>>(),
+                vec![page_url(1), page_url(2), page_url(3)],
+                "prefetch: {prefetch}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn ends_with_error_of_failing_page() {
+        for prefetch in [false, true] {
+            let transport = MemoryTransport::new();
+            insert_page(&transport, 1, &["1", "2"], 6);
+            insert_page(&transport, 3, &["5", "6"], 6);
+
+            let items = collect(transport.clone(), prefetch).await;
+
+            assert_eq!(items.len(), 3, "prefetch: {prefetch}");
+            assert_eq!(items[0].as_ref().unwrap().id, "1");
+            assert_eq!(items[1].as_ref().unwrap().id, "2");
+            assert!(
+                matches!(&items[2], Err(error) if error.kind() == ApiErrorKind::NotFound),
+                "prefetch: {prefetch}"
+            );
+            assert!(!transport
+                .requests()
+                .iter()
+                .any(|request| request.url == page_url(3)));
+        }
+    }
+}
@@ -0,0 +1,249 @@
+use std::{
+    collections::HashMap,
+    future::Future,
+    sync::{Arc, Mutex, PoisonError},
+};
+
+use reqwest::Client;
+
+use super::error::ApiError;
+
+/// A GET request sent by the client through a `Transport`.
+#[derive(Clone, Debug)]
+pub struct TransportRequest {
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+}
+
+impl TransportRequest {
+    /// Creates a new request without headers.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - The complete url of the request.
+    #[must_use]
+    pub fn new(url: &str) -> Self {
+        TransportRequest {
+            url: url.to_owned(),
+            headers: Vec::new(),
+        }
+    }
+
+    /// Adds a header to the request.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the header.
+    /// * `value` - The value of the header.
+    #[must_use]
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_owned(), value.to_owned()));
+        self
+    }
+}
+
+/// The response to a `TransportRequest`.
+#[derive(Clone, Debug)]
+pub struct TransportResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl TransportResponse {
+    /// Creates a new response without headers.
+    ///
+    /// # Arguments
+    ///
+    /// * `status` - The status code of the response.
+    /// * `body` - The raw body of the response.
+    #[must_use]
+    pub fn new(status: u16, body: impl Into<Vec<u8>>) -> Self {
+        TransportResponse {
+            status,
+            headers: Vec::new(),
+            body: body.into(),
+        }
+    }
+
+    /// Adds a header to the response.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the header.
+    /// * `value` - The value of the header.
+    #[must_use]
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_owned(), value.to_owned()));
+        self
+    }
+
+    /// Returns the value of the first header with the given name, ignoring its case.
+    #[must_use]
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Returns whether the status code signals success.
+    #[must_use]
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+}
+
+/// Trait for the http backends used by the `PokemonApiClient`.
+pub trait Transport: Send + Sync + 'static {
+    /// Sends the request and returns the response, regardless of its status code.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - The request to send.
+    ///
+    /// # Errors
+    ///
+    /// Should return `Err` only if no response could be received at all.
+    fn send(
+        &self,
+        request: TransportRequest,
+    ) -> impl Future<Output = Result<TransportResponse, ApiError>> + Send;
+}
+
+/// `Transport` sending requests over the network with a `reqwest::Client`.
+#[derive(Clone, Debug, Default)]
+pub struct ReqwestTransport {
+    client: Client,
+}
+
+impl ReqwestTransport {
+    /// Creates a new transport with a default `reqwest::Client`.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a new transport using a preconfigured `reqwest::Client`.
+    ///
+    /// # Arguments
+    ///
+    /// * `client` - The client to send the requests with.
+    #[must_use]
+    pub fn from_client(client: Client) -> Self {
+        ReqwestTransport { client }
+    }
+}
+
+impl Transport for ReqwestTransport {
+    async fn send(&self, request: TransportRequest) -> Result<TransportResponse, ApiError> {
+        let mut builder = self.client.get(&request.url);
+
+        for (name, value) in &request.headers {
+            builder = builder.header(name, value);
+        }
+
+        let response = builder.send().await?;
+        let status = response.status().as_u16();
+        let headers = response
+            .headers()
+            .iter()
+            .filter_map(|(name, value)| {
+                value
+                    .to_str()
+                    .ok()
+                    .map(|value| (name.as_str().to_owned(), value.to_owned()))
+            })
+            .collect();
+        let body = response.bytes().await?.to_vec();
+
+        Ok(TransportResponse {
+            status,
+            headers,
+            body,
+        })
+    }
+}
+
+/// In memory `Transport` answering requests with canned responses, mainly used for testing.
+/// Urls without a response are answered with a 404 error of the api.
+/// Clones share the same responses and recorded requests.
+#[derive(Clone, Debug, Default)]
+pub struct MemoryTransport {
+    responses: Arc<Mutex<HashMap<String, TransportResponse>>>,
+    requests: Arc<Mutex<Vec<TransportRequest>>>,
+}
+
+impl MemoryTransport {
+    /// Creates a new transport without any responses.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a successful json response for an url.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - The complete url of the request, including the query parameters.
+    /// * `json` - The json body of the response.
+    #[must_use]
+    pub fn with_json(self, url: &str, json: &str) -> Self {
+        self.with_response(url, TransportResponse::new(200, json))
+    }
+
+    /// Adds a response for an url.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - The complete url of the request, including the query parameters.
+    /// * `response` - The response to answer with.
+    #[must_use]
+    pub fn with_response(self, url: &str, response: TransportResponse) -> Self {
+        self.insert(url, response);
+        self
+    }
+
+    /// Adds or replaces the response for an url.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - The complete url of the request, including the query parameters.
+    /// * `response` - The response to answer with.
+    pub fn insert(&self, url: &str, response: TransportResponse) {
+        self.responses
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(url.to_owned(), response);
+    }
+
+    /// Returns all requests received so far, in order.
+    #[must_use]
+    pub fn requests(&self) -> Vec<TransportRequest> {
+        self.requests
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+}
+
+impl Transport for MemoryTransport {
+    async fn send(&self, request: TransportRequest) -> Result<TransportResponse, ApiError> {
+        let response = self
+            .responses
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(&request.url)
+            .cloned()
+            .unwrap_or_else(|| {
+                TransportResponse::new(404, r#"{"error":{"message":"Not Found","code":404}}"#)
+            });
+
+        self.requests
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(request);
+
+        Ok(response)
+    }
+}
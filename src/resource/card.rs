@@ -1,15 +1,13 @@
 use serde::{Deserialize, Serialize};
 
-use crate::pokemon_api_client::api_client::API_URL;
-
 use super::{
     common::{Legalities, PokemonType, Resistance, Weakness},
     set::Set,
-    ApiResource,
+    ApiResource, Identifiable,
 };
 
 /// Subresource for cards in the api.
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Card {
     pub id: String,
     pub name: String,
@@ -41,7 +39,7 @@ pub struct Card {
     #[serde(rename = "nationalPokedexNumbers")]
     pub national_pokedex_numbers: Option<Vec<u32>>,
     pub legalities: Legalities,
-    #[serde(rename = "regulationsMark")]
+    #[serde(rename = "regulationMark", alias = "regulationsMark")]
     pub regulations_mark: Option<String>,
     pub images: Image,
     pub tcgplayer: Option<Tcgplayer>,
@@ -49,115 +47,124 @@ pub struct Card {
 }
 
 impl ApiResource for Card {
-    fn url() -> String {
-        format!("{API_URL}/cards")
+    fn path() -> String {
+        String::from("/cards")
+    }
+}
+
+impl Identifiable for Card {
+    fn id(&self) -> &str {
+        &self.id
     }
 }
 
 /// Subresource for the ancient traits in the api.
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct AncientTrait {
-    name: String,
-    text: String,
+    pub name: String,
+    pub text: String,
 }
 
 /// Subresource for the market infos in the api.
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Market {
-    url: String,
+    pub url: String,
     #[serde(rename = "updatedAt")]
-    updated_at: String,
-    prices: CardmarketPrices,
+    pub updated_at: String,
+    pub prices: CardmarketPrices,
 }
 
 /// Subresource for the cardmarket prices in the api.
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct CardmarketPrices {
     #[serde(rename = "averageSellPrice")]
-    average_sell_price: Option<f32>,
+    pub average_sell_price: Option<f32>,
     #[serde(rename = "lowPrice")]
-    low_price: Option<f32>,
+    pub low_price: Option<f32>,
     #[serde(rename = "trendPrice")]
-    trend_price: Option<f32>,
+    pub trend_price: Option<f32>,
     #[serde(rename = "germanProLow")]
-    german_pro_low: Option<f32>,
+    pub german_pro_low: Option<f32>,
     #[serde(rename = "suggestedPrice")]
-    suggested_price: Option<f32>,
+    pub suggested_price: Option<f32>,
     #[serde(rename = "reverseHoloSell")]
-    reverse_holo_sell: Option<f32>,
+    pub reverse_holo_sell: Option<f32>,
     #[serde(rename = "reverseHoloLow")]
-    reverse_holo_low: Option<f32>,
+    pub reverse_holo_low: Option<f32>,
     #[serde(rename = "reverseHoloTrend")]
-    reverse_holo_trend: Option<f32>,
+    pub reverse_holo_trend: Option<f32>,
     #[serde(rename = "lowPriceExPlus")]
-    low_price_ex_plus: Option<f32>,
+    pub low_price_ex_plus: Option<f32>,
     #[serde(rename = "avg1")]
-    average_day: Option<f32>,
+    pub average_day: Option<f32>,
     #[serde(rename = "avg7")]
-    average_week: Option<f32>,
-    #[serde(rename = "avg9")]
-    average_month: Option<f32>,
+    pub average_week: Option<f32>,
+    #[serde(rename = "avg30", alias = "avg9")]
+    pub average_month: Option<f32>,
     #[serde(rename = "reverseHoloAvg1")]
-    reverse_holo_avg1: Option<f32>,
+    pub reverse_holo_avg1: Option<f32>,
     #[serde(rename = "reverseHoloAvg7")]
-    reverse_holo_avg7: Option<f32>,
+    pub reverse_holo_avg7: Option<f32>,
     #[serde(rename = "reverseHoloAvg30")]
-    reverse_holo_avg30: Option<f32>,
+    pub reverse_holo_avg30: Option<f32>,
 }
 
 /// Subresource for the images of cards in the api.
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Image {
     pub small: String,
     pub large: String,
 }
 
 /// Subresource for the for tcg player data in the api.
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Tcgplayer {
-    url: String,
+    pub url: String,
     #[serde(rename = "updatedAt")]
-    updated_at: String,
-    prices: Option<TcgplayerPrices>,
+    pub updated_at: String,
+    pub prices: Option<TcgplayerPrices>,
 }
 
 /// Subresource for the tcg player price list in the api.
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct TcgplayerPrices {
-    normal: Option<TcgplayerPrice>,
-    holofoil: Option<TcgplayerPrice>,
+    pub normal: Option<TcgplayerPrice>,
+    pub holofoil: Option<TcgplayerPrice>,
+    #[serde(rename = "reverseHolofoil")]
+    pub reverse_holofoil: Option<TcgplayerPrice>,
     #[serde(rename = "1stEditionHolofoil")]
-    first_edition_holofoil: Option<TcgplayerPrice>,
+    pub first_edition_holofoil: Option<TcgplayerPrice>,
     #[serde(rename = "1stEditionNormal")]
-    first_edition_normal: Option<TcgplayerPrice>,
+    pub first_edition_normal: Option<TcgplayerPrice>,
 }
 
 /// Subresource for the tcg player prices in the api.
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct TcgplayerPrice {
-    low: f32,
-    mid: f32,
-    high: f32,
-    market: Option<f32>,
-    direct_low: Option<f32>,
+    pub low: f32,
+    pub mid: f32,
+    pub high: f32,
+    pub market: Option<f32>,
+    #[serde(rename = "directLow", alias = "direct_low")]
+    pub direct_low: Option<f32>,
 }
 
 /// Subresource for the attacks in the api.
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Attack {
-    name: String,
-    cost: Vec<PokemonType>,
+    pub name: String,
+    pub cost: Vec<PokemonType>,
     #[serde(rename = "convertedEnergyCost")]
-    converted_energy_cost: u32,
-    damage: String,
-    text: String,
+    pub converted_energy_cost: u32,
+    pub damage: String,
+    pub text: String,
 }
 
 /// Subresource for the abilities in the api.
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Ability {
-    name: String,
-    text: String,
+    pub name: String,
+    pub text: String,
     #[serde(rename = "type")]
-    ability_type: String,
+    pub ability_type: String,
 }
@@ -1,38 +1,38 @@
 use serde::{Deserialize, Serialize};
 
 /// Subresource for the legalities in the api.
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Legalities {
-    standard: Option<String>,
-    unlimited: Option<String>,
-    expanded: Option<String>,
+    pub standard: Option<String>,
+    pub unlimited: Option<String>,
+    pub expanded: Option<String>,
 }
 
 /// Subresource for the Images in the api.
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Images {
-    symbol: String,
-    logo: String,
+    pub symbol: String,
+    pub logo: String,
 }
 
 /// Subresource for the weaknesses in the api.
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Weakness {
     #[serde(rename = "type")]
-    weakness_type: PokemonType,
-    value: String,
+    pub weakness_type: PokemonType,
+    pub value: String,
 }
 
 /// Subresource for the resistances in the api.
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Resistance {
     #[serde(rename = "type")]
-    resistance_type: PokemonType,
-    value: String,
+    pub resistance_type: PokemonType,
+    pub value: String,
 }
 
 /// Subresource for the pokemon types in the api.
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub enum PokemonType {
     Colorless,
     Grass,
@@ -48,7 +48,7 @@ pub enum PokemonType {
 }
 
 /// Subresource for the supertypes in the api.
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub enum Supertype {
     Energy,
     Pokemon,
@@ -2,15 +2,31 @@
 
 pub mod card;
 pub mod common;
+pub mod partial;
 pub mod set;
 
 /// Trait for using the api client.
 pub trait ApiResource {
-    fn url() -> String;
+    /// Returns the path of the resource relative to the base url of the api, e.g. `/cards`.
+    fn path() -> String;
+
+    /// Returns the url of the resource resolved against a base url.
+    ///
+    /// # Arguments
+    ///
+    /// * `base_url` - The base url of the api.
+    fn url(base_url: &str) -> String {
+        format!("{}{}", base_url.trim_end_matches('/'), Self::path())
+    }
 }
 
 impl<T: ApiResource> ApiResource for Vec<T> {
-    fn url() -> String {
-        T::url()
+    fn path() -> String {
+        T::path()
     }
 }
+
+/// Trait for resources which are identified by a unique id in the api.
+pub trait Identifiable {
+    fn id(&self) -> &str;
+}
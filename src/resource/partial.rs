@@ -0,0 +1,194 @@
+use serde::{Deserialize, Serialize};
+
+use super::{
+    card::{Ability, AncientTrait, Attack, Card, Image, Market, Tcgplayer},
+    common::{Images, Legalities, PokemonType, Resistance, Weakness},
+    set::Set,
+    ApiResource,
+};
+
+/// `ApiResource` for cards of which only some fields were selected, e.g. with `add_select`.
+/// Every field is optional and only contains a value if it was selected.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct PartialCard {
+    pub id: Option<String>,
+    pub name: Option<String>,
+    pub supertype: Option<String>,
+    pub subtypes: Option<Vec<String>>,
+    pub hp: Option<String>,
+    pub types: Option<Vec<PokemonType>>,
+    #[serde(rename = "evolvesFrom")]
+    pub evolves_from: Option<String>,
+    #[serde(rename = "evolvesTo")]
+    pub evolves_to: Option<Vec<String>>,
+    pub rules: Option<Vec<String>>,
+    pub abilities: Option<Vec<Ability>>,
+    #[serde(rename = "ancientTrait")]
+    pub ancient_trait: Option<AncientTrait>,
+    pub attacks: Option<Vec<Attack>>,
+    pub weaknesses: Option<Vec<Weakness>>,
+    pub resistances: Option<Vec<Resistance>>,
+    #[serde(rename = "retreatCost")]
+    pub retreat_cost: Option<Vec<PokemonType>>,
+    #[serde(rename = "convertedRetreatCost")]
+    pub converted_retreat_cost: Option<u32>,
+    /// The set of the card, which is partial as well when only some of its fields were selected.
+    pub set: Option<PartialSet>,
+    pub number: Option<String>,
+    pub artist: Option<String>,
+    pub rarity: Option<String>,
+    #[serde(rename = "flavorText")]
+    pub flavor_text: Option<String>,
+    #[serde(rename = "nationalPokedexNumbers")]
+    pub national_pokedex_numbers: Option<Vec<u32>>,
+    pub legalities: Option<Legalities>,
+    #[serde(rename = "regulationMark", alias = "regulationsMark")]
+    pub regulations_mark: Option<String>,
+    pub images: Option<Image>,
+    pub tcgplayer: Option<Tcgplayer>,
+    pub cardmarket: Option<Market>,
+}
+
+/// `ApiResource` for sets of which only some fields were selected, e.g. with `add_select`.
+/// Every field is optional and only contains a value if it was selected.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct PartialSet {
+    pub id: Option<String>,
+    pub name: Option<String>,
+    pub series: Option<String>,
+    #[serde(rename = "printedTotal")]
+    pub printed_total: Option<u32>,
+    pub total: Option<u32>,
+    pub legalities: Option<Legalities>,
+    #[serde(rename = "ptcgoCode")]
+    pub ptcgo_code: Option<String>,
+    #[serde(rename = "releaseDate")]
+    pub release_date: Option<String>,
+    #[serde(rename = "updatedAt")]
+    pub updated_at: Option<String>,
+    pub images: Option<Images>,
+}
+
+impl ApiResource for PartialCard {
+    fn path() -> String {
+        String::from("/cards")
+    }
+}
+
+impl ApiResource for PartialSet {
+    fn path() -> String {
+        String::from("/sets")
+    }
+}
+
+impl From<Card> for PartialCard {
+    fn from(card: Card) -> Self {
+        PartialCard {
+            id: Some(card.id),
+            name: Some(card.name),
+            supertype: Some(card.supertype),
+            subtypes: card.subtypes,
+            hp: card.hp,
+            types: card.types,
+            evolves_from: card.evolves_from,
+            evolves_to: card.evolves_to,
+            rules: card.rules,
+            abilities: card.abilities,
+            ancient_trait: card.ancient_trait,
+            attacks: card.attacks,
+            weaknesses: card.weaknesses,
+            resistances: card.resistances,
+            retreat_cost: card.retreat_cost,
+            converted_retreat_cost: card.converted_retreat_cost,
+            set: Some(PartialSet::from(card.set)),
+            number: Some(card.number),
+            artist: card.artist,
+            rarity: card.rarity,
+            flavor_text: card.flavor_text,
+            national_pokedex_numbers: card.national_pokedex_numbers,
+            legalities: Some(card.legalities),
+            regulations_mark: card.regulations_mark,
+            images: Some(card.images),
+            tcgplayer: card.tcgplayer,
+            cardmarket: card.cardmarket,
+        }
+    }
+}
+
+impl From<Set> for PartialSet {
+    fn from(set: Set) -> Self {
+        PartialSet {
+            id: Some(set.id),
+            name: Some(set.name),
+            series: Some(set.series),
+            printed_total: Some(set.printed_total),
+            total: Some(set.total),
+            legalities: Some(set.legalities),
+            ptcgo_code: set.ptcgo_code,
+            release_date: Some(set.release_date),
+            updated_at: Some(set.updated_at),
+            images: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deserializes_selected_fields() {
+        let card: PartialCard = serde_json::from_str(
+            r#"{
+                "id": "swsh4-25",
+                "name": "Charizard",
+                "regulationMark": "D",
+                "set": { "id": "swsh4", "series": "Sword & Shield" }
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(card.id.as_deref(), Some("swsh4-25"));
+        assert_eq!(card.name.as_deref(), Some("Charizard"));
+        assert_eq!(card.regulations_mark.as_deref(), Some("D"));
+        assert!(card.hp.is_none());
+        assert!(card.legalities.is_none());
+
+        let set = card.set.unwrap();
+        assert_eq!(set.id.as_deref(), Some("swsh4"));
+        assert_eq!(set.series.as_deref(), Some("Sword & Shield"));
+        assert!(set.name.is_none());
+    }
+
+    #[test]
+    fn reads_previous_regulation_mark_name() {
+        let card: PartialCard = serde_json::from_str(r#"{ "regulationsMark": "E" }"#).unwrap();
+        let full: Card = serde_json::from_value(serde_json::json!({
+            "id": "swsh4-25",
+            "name": "Charizard",
+            "supertype": "Pokémon",
+            "set": {
+                "id": "swsh4",
+                "name": "Vivid Voltage",
+                "series": "Sword & Shield",
+                "printedTotal": 185,
+                "total": 203,
+                "legalities": { "unlimited": "Legal" },
+                "releaseDate": "2020/11/13",
+                "updatedAt": "2020/11/13 16:20:00"
+            },
+            "number": "25",
+            "legalities": { "unlimited": "Legal" },
+            "regulationsMark": "E",
+            "images": { "small": "small.png", "large": "large.png" }
+        }))
+        .unwrap();
+
+        assert_eq!(card.regulations_mark.as_deref(), Some("E"));
+        assert_eq!(full.regulations_mark.as_deref(), Some("E"));
+        assert_eq!(
+            serde_json::to_value(&full).unwrap()["regulationMark"],
+            serde_json::json!("E")
+        );
+    }
+}
@@ -1,11 +1,9 @@
 use serde::{Deserialize, Serialize};
 
-use crate::pokemon_api_client::api_client::API_URL;
-
-use super::{common::Legalities, ApiResource};
+use super::{common::Legalities, ApiResource, Identifiable};
 
 /// `ApiResource` for sets.
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Set {
     pub id: String,
     pub name: String,
@@ -23,7 +21,13 @@ pub struct Set {
 }
 
 impl ApiResource for Set {
-    fn url() -> String {
-        format!("{API_URL}/sets")
+    fn path() -> String {
+        String::from("/sets")
+    }
+}
+
+impl Identifiable for Set {
+    fn id(&self) -> &str {
+        &self.id
     }
 }
@@ -0,0 +1,3 @@
+//! Contains the storage backends for persisting the catalogue.
+//! Requires the `sqlite` feature.
+pub mod sqlite;
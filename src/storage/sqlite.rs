@@ -0,0 +1,1241 @@
+use std::{collections::HashMap, path::Path};
+
+use rusqlite::{params, Connection, OptionalExtension, Params, Row, Transaction};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+
+use crate::{
+    pokemon_api_client::{
+        api_client::{CardId, PokemonApiClient, SetId},
+        error::ApiError,
+        transport::Transport,
+    },
+    resource::{
+        card::{
+            Ability, AncientTrait, Attack, Card, CardmarketPrices, Image, Market, Tcgplayer,
+            TcgplayerPrice, TcgplayerPrices,
+        },
+        common::{Legalities, Resistance, Weakness},
+        set::Set,
+    },
+};
+
+/// Migrations of the schema, the schema version is the amount of applied migrations.
+static MIGRATIONS: &[&str] = &[r"
+    CREATE TABLE sets (
+        id TEXT PRIMARY KEY NOT NULL,
+        name TEXT NOT NULL,
+        series TEXT NOT NULL,
+        printed_total INTEGER NOT NULL,
+        total INTEGER NOT NULL,
+        ptcgo_code TEXT,
+        release_date TEXT NOT NULL,
+        updated_at TEXT NOT NULL,
+        standard_legality TEXT,
+        unlimited_legality TEXT,
+        expanded_legality TEXT
+    );
+
+    CREATE TABLE cards (
+        id TEXT PRIMARY KEY NOT NULL,
+        name TEXT NOT NULL,
+        supertype TEXT NOT NULL,
+        hp TEXT,
+        evolves_from TEXT,
+        ancient_trait_name TEXT,
+        ancient_trait_text TEXT,
+        converted_retreat_cost INTEGER,
+        set_id TEXT NOT NULL REFERENCES sets (id),
+        number TEXT NOT NULL,
+        artist TEXT,
+        rarity TEXT,
+        flavor_text TEXT,
+        regulation_mark TEXT,
+        image_small TEXT NOT NULL,
+        image_large TEXT NOT NULL,
+        standard_legality TEXT,
+        unlimited_legality TEXT,
+        expanded_legality TEXT
+    );
+    CREATE INDEX cards_set_id ON cards (set_id);
+    CREATE INDEX cards_name ON cards (name);
+
+    CREATE TABLE card_subtypes (
+        card_id TEXT NOT NULL REFERENCES cards (id) ON DELETE CASCADE,
+        position INTEGER NOT NULL,
+        subtype TEXT NOT NULL,
+        PRIMARY KEY (card_id, position)
+    );
+
+    CREATE TABLE card_types (
+        card_id TEXT NOT NULL REFERENCES cards (id) ON DELETE CASCADE,
+        position INTEGER NOT NULL,
+        type TEXT NOT NULL,
+        PRIMARY KEY (card_id, position)
+    );
+
+    CREATE TABLE card_evolves_to (
+        card_id TEXT NOT NULL REFERENCES cards (id) ON DELETE CASCADE,
+        position INTEGER NOT NULL,
+        name TEXT NOT NULL,
+        PRIMARY KEY (card_id, position)
+    );
+
+    CREATE TABLE card_rules (
+        card_id TEXT NOT NULL REFERENCES cards (id) ON DELETE CASCADE,
+        position INTEGER NOT NULL,
+        rule TEXT NOT NULL,
+        PRIMARY KEY (card_id, position)
+    );
+
+    CREATE TABLE card_retreat_costs (
+        card_id TEXT NOT NULL REFERENCES cards (id) ON DELETE CASCADE,
+        position INTEGER NOT NULL,
+        type TEXT NOT NULL,
+        PRIMARY KEY (card_id, position)
+    );
+
+    CREATE TABLE card_national_pokedex_numbers (
+        card_id TEXT NOT NULL REFERENCES cards (id) ON DELETE CASCADE,
+        position INTEGER NOT NULL,
+        number INTEGER NOT NULL,
+        PRIMARY KEY (card_id, position)
+    );
+
+    CREATE TABLE attacks (
+        card_id TEXT NOT NULL REFERENCES cards (id) ON DELETE CASCADE,
+        position INTEGER NOT NULL,
+        name TEXT NOT NULL,
+        converted_energy_cost INTEGER NOT NULL,
+        damage TEXT NOT NULL,
+        text TEXT NOT NULL,
+        PRIMARY KEY (card_id, position)
+    );
+
+    CREATE TABLE attack_costs (
+        card_id TEXT NOT NULL REFERENCES cards (id) ON DELETE CASCADE,
+        attack_position INTEGER NOT NULL,
+        position INTEGER NOT NULL,
+        type TEXT NOT NULL,
+        PRIMARY KEY (card_id, attack_position, position)
+    );
+
+    CREATE TABLE abilities (
+        card_id TEXT NOT NULL REFERENCES cards (id) ON DELETE CASCADE,
+        position INTEGER NOT NULL,
+        name TEXT NOT NULL,
+        text TEXT NOT NULL,
+        type TEXT NOT NULL,
+        PRIMARY KEY (card_id, position)
+    );
+
+    CREATE TABLE weaknesses (
+        card_id TEXT NOT NULL REFERENCES cards (id) ON DELETE CASCADE,
+        position INTEGER NOT NULL,
+        type TEXT NOT NULL,
+        value TEXT NOT NULL,
+        PRIMARY KEY (card_id, position)
+    );
+
+    CREATE TABLE resistances (
+        card_id TEXT NOT NULL REFERENCES cards (id) ON DELETE CASCADE,
+        position INTEGER NOT NULL,
+        type TEXT NOT NULL,
+        value TEXT NOT NULL,
+        PRIMARY KEY (card_id, position)
+    );
+
+    CREATE TABLE tcgplayer (
+        card_id TEXT PRIMARY KEY NOT NULL REFERENCES cards (id) ON DELETE CASCADE,
+        url TEXT NOT NULL,
+        updated_at TEXT NOT NULL
+    );
+
+    CREATE TABLE tcgplayer_prices (
+        card_id TEXT NOT NULL REFERENCES cards (id) ON DELETE CASCADE,
+        variant TEXT NOT NULL,
+        low REAL NOT NULL,
+        mid REAL NOT NULL,
+        high REAL NOT NULL,
+        market REAL,
+        direct_low REAL,
+        PRIMARY KEY (card_id, variant)
+    );
+
+    CREATE TABLE cardmarket (
+        card_id TEXT PRIMARY KEY NOT NULL REFERENCES cards (id) ON DELETE CASCADE,
+        url TEXT NOT NULL,
+        updated_at TEXT NOT NULL,
+        average_sell_price REAL,
+        low_price REAL,
+        trend_price REAL,
+        german_pro_low REAL,
+        suggested_price REAL,
+        reverse_holo_sell REAL,
+        reverse_holo_low REAL,
+        reverse_holo_trend REAL,
+        low_price_ex_plus REAL,
+        avg1 REAL,
+        avg7 REAL,
+        avg30 REAL,
+        reverse_holo_avg1 REAL,
+        reverse_holo_avg7 REAL,
+        reverse_holo_avg30 REAL
+    );
+"];
+
+/// Tables holding the lists of a card, with the name of their value column.
+static LIST_TABLES: &[(&str, &str)] = &[
+    ("card_subtypes", "subtype"),
+    ("card_types", "type"),
+    ("card_evolves_to", "name"),
+    ("card_rules", "rule"),
+    ("card_retreat_costs", "type"),
+];
+
+/// Tables holding data which belongs to exactly one card.
+static CARD_CHILD_TABLES: &[&str] = &[
+    "card_subtypes",
+    "card_types",
+    "card_evolves_to",
+    "card_rules",
+    "card_retreat_costs",
+    "card_national_pokedex_numbers",
+    "attacks",
+    "attack_costs",
+    "abilities",
+    "weaknesses",
+    "resistances",
+    "tcgplayer",
+    "tcgplayer_prices",
+    "cardmarket",
+];
+
+/// Amount of cards and sets written by an upsert from the api.
+#[derive(Debug, Clone, Copy)]
+pub struct UpsertSummary {
+    pub set_count: usize,
+    pub card_count: usize,
+}
+
+/// Storage of the catalogue in a normalized sqlite database.
+/// The schema is created and migrated when the database is opened.
+pub struct SqliteStore {
+    connection: Connection,
+}
+
+impl SqliteStore {
+    /// Opens or creates the database file and migrates it to the current schema.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the database file.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the database cannot be opened or migrated.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, ApiError> {
+        Self::from_connection(Connection::open(path)?)
+    }
+
+    /// Creates a database in memory with the current schema.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the database cannot be created.
+    pub fn open_in_memory() -> Result<Self, ApiError> {
+        Self::from_connection(Connection::open_in_memory()?)
+    }
+
+    /// Uses an already opened connection and migrates its database to the current schema.
+    ///
+    /// # Arguments
+    ///
+    /// * `connection` - The connection to the database.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the database cannot be migrated.
+    pub fn from_connection(mut connection: Connection) -> Result<Self, ApiError> {
+        connection.pragma_update(None, "foreign_keys", true)?;
+        migrate(&mut connection)?;
+
+        Ok(SqliteStore { connection })
+    }
+
+    /// Returns the connection to the database, e.g. for running own sql queries.
+    #[must_use]
+    pub fn connection(&self) -> &Connection {
+        &self.connection
+    }
+
+    /// Returns the version of the schema of the database.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the version cannot be read.
+    pub fn schema_version(&self) -> Result<u32, ApiError> {
+        schema_version(&self.connection)
+    }
+
+    /// Inserts the sets or updates them if they already exist.
+    ///
+    /// # Arguments
+    ///
+    /// * `sets` - The sets to write.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the sets cannot be written, in which case none of them are.
+    pub fn upsert_sets(&mut self, sets: &[Set]) -> Result<(), ApiError> {
+        let transaction = self.connection.transaction()?;
+
+        for set in sets {
+            upsert_set(&transaction, set)?;
+        }
+
+        Ok(transaction.commit()?)
+    }
+
+    /// Inserts the cards and their sets or updates them if they already exist.
+    /// Lists, attacks and prices of existing cards are replaced.
+    ///
+    /// # Arguments
+    ///
+    /// * `cards` - The cards to write.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the cards cannot be written, in which case none of them are.
+    pub fn upsert_cards(&mut self, cards: &[Card]) -> Result<(), ApiError> {
+        let transaction = self.connection.transaction()?;
+
+        for card in cards {
+            upsert_set(&transaction, &card.set)?;
+            upsert_card(&transaction, card)?;
+        }
+
+        Ok(transaction.commit()?)
+    }
+
+    /// Fetches every set and card from the api and upserts them.
+    ///
+    /// # Arguments
+    ///
+    /// * `client` - The client used to query the api.
+    /// * `max_concurrency` - The maximum amount of pages fetched at the same time.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if not every page could be fetched or the data cannot be written.
+    pub async fn upsert_from_api<Tr: Transport>(
+        &mut self,
+        client: &PokemonApiClient<Tr>,
+        max_concurrency: usize,
+    ) -> Result<UpsertSummary, ApiError> {
+        let sets = client.get_all_sets_exhaustive(max_concurrency).await?;
+        if let Some(failure) = sets.failed_pages.into_iter().next() {
+            return Err(failure.error);
+        }
+
+        let cards = client.get_all_cards_exhaustive(max_concurrency).await?;
+        if let Some(failure) = cards.failed_pages.into_iter().next() {
+            return Err(failure.error);
+        }
+
+        self.upsert_sets(&sets.data)?;
+        self.upsert_cards(&cards.data)?;
+
+        Ok(UpsertSummary {
+            set_count: sets.data.len(),
+            card_count: cards.data.len(),
+        })
+    }
+
+    /// Reads a set based on its id.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The id of the set to read.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the set cannot be read.
+    pub fn get_set(&self, id: &SetId) -> Result<Option<Set>, ApiError> {
+        Ok(self
+            .connection
+            .query_row(&format!("{SET_COLUMNS} WHERE id = ?1"), [&id.0], read_set)
+            .optional()?)
+    }
+
+    /// Reads all sets ordered by their release date.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the sets cannot be read.
+    pub fn get_all_sets(&self) -> Result<Vec<Set>, ApiError> {
+        let mut statement = self
+            .connection
+            .prepare(&format!("{SET_COLUMNS} ORDER BY release_date, id"))?;
+        let sets = statement
+            .query_map([], read_set)?
+            .collect::<Result<Vec<Set>, rusqlite::Error>>()?;
+
+        Ok(sets)
+    }
+
+    /// Reads a card based on its id.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The id of the card to read.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the card cannot be read.
+    pub fn get_card(&self, id: &CardId) -> Result<Option<Card>, ApiError> {
+        Ok(self
+            .read_cards("WHERE id = ?1", [&id.0])?
+            .into_iter()
+            .next())
+    }
+
+    /// Reads all cards of a set.
+    ///
+    /// # Arguments
+    ///
+    /// * `set_id` - The id of the set.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the cards cannot be read.
+    pub fn get_cards_of_set(&self, set_id: &SetId) -> Result<Vec<Card>, ApiError> {
+        self.read_cards("WHERE set_id = ?1", [&set_id.0])
+    }
+
+    /// Reads all cards.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the cards cannot be read.
+    pub fn get_all_cards(&self) -> Result<Vec<Card>, ApiError> {
+        self.read_cards("", [])
+    }
+
+    /// Reads the cards matching the filter, loading every child table with one query.
+    /// The filter is applied to the cards table and has to use the same parameters everywhere.
+    fn read_cards<P: Params + Copy>(&self, filter: &str, params: P) -> Result<Vec<Card>, ApiError> {
+        let card_ids = format!("SELECT id FROM cards {filter}");
+
+        let sets = {
+            let mut statement = self.connection.prepare(&format!(
+                "{SET_COLUMNS} WHERE id IN (SELECT set_id FROM cards {filter})"
+            ))?;
+            let sets = statement
+                .query_map(params, read_set)?
+                .collect::<Result<Vec<Set>, rusqlite::Error>>()?;
+
+            sets.into_iter()
+                .map(|set| (set.id.clone(), set))
+                .collect::<HashMap<String, Set>>()
+        };
+
+        let mut lists = HashMap::new();
+        for (table, column) in LIST_TABLES {
+            lists.insert(
+                *table,
+                self.group_by_card(
+                    &format!("SELECT card_id, {column} FROM {table} WHERE card_id IN ({card_ids}) ORDER BY card_id, position"),
+                    params,
+                    |row| row.get::<_, String>(1),
+                )?,
+            );
+        }
+
+        let mut pokedex_numbers = self.group_by_card(
+            &format!("SELECT card_id, number FROM card_national_pokedex_numbers WHERE card_id IN ({card_ids}) ORDER BY card_id, position"),
+            params,
+            |row| row.get::<_, u32>(1),
+        )?;
+        let mut attack_costs = self.group_by_card(
+            &format!("SELECT card_id, attack_position, type FROM attack_costs WHERE card_id IN ({card_ids}) ORDER BY card_id, attack_position, position"),
+            params,
+            |row| Ok((row.get::<_, u32>(1)?, row.get::<_, String>(2)?)),
+        )?;
+        let attacks = self.group_by_card(
+            &format!("SELECT card_id, position, name, converted_energy_cost, damage, text FROM attacks WHERE card_id IN ({card_ids}) ORDER BY card_id, position"),
+            params,
+            |row| {
+                Ok((
+                    row.get::<_, u32>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, u32>(3)?,
+                    row.get::<_, String>(4)?,
+                    row.get::<_, String>(5)?,
+                ))
+            },
+        )?;
+        let mut abilities = self.group_by_card(
+            &format!("SELECT card_id, name, text, type FROM abilities WHERE card_id IN ({card_ids}) ORDER BY card_id, position"),
+            params,
+            |row| {
+                Ok(Ability {
+                    name: row.get(1)?,
+                    text: row.get(2)?,
+                    ability_type: row.get(3)?,
+                })
+            },
+        )?;
+        let weaknesses = self.group_by_card(
+            &format!("SELECT card_id, type, value FROM weaknesses WHERE card_id IN ({card_ids}) ORDER BY card_id, position"),
+            params,
+            |row| Ok((row.get::<_, String>(1)?, row.get::<_, String>(2)?)),
+        )?;
+        let resistances = self.group_by_card(
+            &format!("SELECT card_id, type, value FROM resistances WHERE card_id IN ({card_ids}) ORDER BY card_id, position"),
+            params,
+            |row| Ok((row.get::<_, String>(1)?, row.get::<_, String>(2)?)),
+        )?;
+        let mut tcgplayers = self.group_by_card(
+            &format!(
+                "SELECT card_id, url, updated_at FROM tcgplayer WHERE card_id IN ({card_ids})"
+            ),
+            params,
+            |row| Ok((row.get::<_, String>(1)?, row.get::<_, String>(2)?)),
+        )?;
+        let mut tcgplayer_prices = self.group_by_card(
+            &format!("SELECT card_id, variant, low, mid, high, market, direct_low FROM tcgplayer_prices WHERE card_id IN ({card_ids})"),
+            params,
+            |row| {
+                Ok((
+                    row.get::<_, String>(1)?,
+                    TcgplayerPrice {
+                        low: row.get(2)?,
+                        mid: row.get(3)?,
+                        high: row.get(4)?,
+                        market: row.get(5)?,
+                        direct_low: row.get(6)?,
+                    },
+                ))
+            },
+        )?;
+        let mut cardmarkets = self.group_by_card(
+            &format!("SELECT card_id, url, updated_at, average_sell_price, low_price, trend_price, german_pro_low, suggested_price, reverse_holo_sell, reverse_holo_low, reverse_holo_trend, low_price_ex_plus, avg1, avg7, avg30, reverse_holo_avg1, reverse_holo_avg7, reverse_holo_avg30 FROM cardmarket WHERE card_id IN ({card_ids})"),
+            params,
+            |row| {
+                Ok(Market {
+                    url: row.get(1)?,
+                    updated_at: row.get(2)?,
+                    prices: CardmarketPrices {
+                        average_sell_price: row.get(3)?,
+                        low_price: row.get(4)?,
+                        trend_price: row.get(5)?,
+                        german_pro_low: row.get(6)?,
+                        suggested_price: row.get(7)?,
+                        reverse_holo_sell: row.get(8)?,
+                        reverse_holo_low: row.get(9)?,
+                        reverse_holo_trend: row.get(10)?,
+                        low_price_ex_plus: row.get(11)?,
+                        average_day: row.get(12)?,
+                        average_week: row.get(13)?,
+                        average_month: row.get(14)?,
+                        reverse_holo_avg1: row.get(15)?,
+                        reverse_holo_avg7: row.get(16)?,
+                        reverse_holo_avg30: row.get(17)?,
+                    },
+                })
+            },
+        )?;
+
+        let mut statement = self.connection.prepare(&format!(
+            "SELECT id, name, supertype, hp, evolves_from, ancient_trait_name, ancient_trait_text, converted_retreat_cost, set_id, number, artist, rarity, flavor_text, regulation_mark, image_small, image_large, standard_legality, unlimited_legality, expanded_legality FROM cards {filter} ORDER BY rowid"
+        ))?;
+        let rows = statement
+            .query_map(params, |row| {
+                Ok(CardRow {
+                    id: row.get(0)?,
+                    name: row.get(1)?,
+                    supertype: row.get(2)?,
+                    hp: row.get(3)?,
+                    evolves_from: row.get(4)?,
+                    ancient_trait_name: row.get(5)?,
+                    ancient_trait_text: row.get(6)?,
+                    converted_retreat_cost: row.get(7)?,
+                    set_id: row.get(8)?,
+                    number: row.get(9)?,
+                    artist: row.get(10)?,
+                    rarity: row.get(11)?,
+                    flavor_text: row.get(12)?,
+                    regulations_mark: row.get(13)?,
+                    images: Image {
+                        small: row.get(14)?,
+                        large: row.get(15)?,
+                    },
+                    legalities: Legalities {
+                        standard: row.get(16)?,
+                        unlimited: row.get(17)?,
+                        expanded: row.get(18)?,
+                    },
+                })
+            })?
+            .collect::<Result<Vec<CardRow>, rusqlite::Error>>()?;
+
+        let mut take_list = |table: &str, id: &str| {
+            lists
+                .get_mut(table)
+                .and_then(|list: &mut HashMap<String, Vec<String>>| list.remove(id))
+        };
+
+        let mut cards = Vec::with_capacity(rows.len());
+        for row in rows {
+            let id = row.id.clone();
+            let set = sets.get(&row.set_id).cloned().ok_or_else(|| {
+                ApiError::General(format!("Set {} of card {id} is missing", row.set_id))
+            })?;
+
+            let costs = attack_costs.remove(&id).unwrap_or_default();
+            let attacks = attacks
+                .get(&id)
+                .map(|attacks| {
+                    attacks
+                        .iter()
+                        .map(|(position, name, converted_energy_cost, damage, text)| {
+                            let cost = costs
+                                .iter()
+                                .filter(|(attack_position, _)| attack_position == position)
+                                .map(|(_, cost)| parse_enum(cost))
+                                .collect::<Result<Vec<_>, ApiError>>()?;
+
+                            Ok(Attack {
+                                name: name.clone(),
+                                cost,
+                                converted_energy_cost: *converted_energy_cost,
+                                damage: damage.clone(),
+                                text: text.clone(),
+                            })
+                        })
+                        .collect::<Result<Vec<Attack>, ApiError>>()
+                })
+                .transpose()?;
+
+            let weaknesses = weaknesses
+                .get(&id)
+                .map(|weaknesses| {
+                    weaknesses
+                        .iter()
+                        .map(|(weakness_type, value)| {
+                            Ok(Weakness {
+                                weakness_type: parse_enum(weakness_type)?,
+                                value: value.clone(),
+                            })
+                        })
+                        .collect::<Result<Vec<Weakness>, ApiError>>()
+                })
+                .transpose()?;
+            let resistances = resistances
+                .get(&id)
+                .map(|resistances| {
+                    resistances
+                        .iter()
+                        .map(|(resistance_type, value)| {
+                            Ok(Resistance {
+                                resistance_type: parse_enum(resistance_type)?,
+                                value: value.clone(),
+                            })
+                        })
+                        .collect::<Result<Vec<Resistance>, ApiError>>()
+                })
+                .transpose()?;
+
+            let tcgplayer = tcgplayers
+                .remove(&id)
+                .and_then(|mut tcgplayers| tcgplayers.pop())
+                .map(|(url, updated_at)| {
+                    let prices = tcgplayer_prices.remove(&id).map(|variants| {
+                        let mut prices = TcgplayerPrices {
+                            normal: None,
+                            holofoil: None,
+                            reverse_holofoil: None,
+                            first_edition_holofoil: None,
+                            first_edition_normal: None,
+                        };
+
+                        for (variant, price) in variants {
+                            match variant.as_str() {
+                                "normal" => prices.normal = Some(price),
+                                "holofoil" => prices.holofoil = Some(price),
+                                "reverseHolofoil" => prices.reverse_holofoil = Some(price),
+                                "1stEditionHolofoil" => prices.first_edition_holofoil = Some(price),
+                                "1stEditionNormal" => prices.first_edition_normal = Some(price),
+                                _ => {}
+                            }
+                        }
+
+                        prices
+                    });
+
+                    Tcgplayer {
+                        url,
+                        updated_at,
+                        prices,
+                    }
+                });
+
+            cards.push(Card {
+                subtypes: take_list("card_subtypes", &id),
+                types: take_list("card_types", &id)
+                    .map(|types| types.iter().map(|value| parse_enum(value)).collect())
+                    .transpose()?,
+                evolves_to: take_list("card_evolves_to", &id),
+                rules: take_list("card_rules", &id),
+                retreat_cost: take_list("card_retreat_costs", &id)
+                    .map(|costs| costs.iter().map(|value| parse_enum(value)).collect())
+                    .transpose()?,
+                national_pokedex_numbers: pokedex_numbers.remove(&id),
+                abilities: abilities.remove(&id),
+                ancient_trait: match (row.ancient_trait_name, row.ancient_trait_text) {
+                    (Some(name), Some(text)) => Some(AncientTrait { name, text }),
+                    _ => None,
+                },
+                attacks,
+                weaknesses,
+                resistances,
+                tcgplayer,
+                cardmarket: cardmarkets
+                    .remove(&id)
+                    .and_then(|mut cardmarkets| cardmarkets.pop()),
+                id,
+                name: row.name,
+                supertype: row.supertype,
+                hp: row.hp,
+                evolves_from: row.evolves_from,
+                converted_retreat_cost: row.converted_retreat_cost,
+                set,
+                number: row.number,
+                artist: row.artist,
+                rarity: row.rarity,
+                flavor_text: row.flavor_text,
+                legalities: row.legalities,
+                regulations_mark: row.regulations_mark,
+                images: row.images,
+            });
+        }
+
+        Ok(cards)
+    }
+
+    /// Runs a query whose first column is the card id and groups the mapped rows by it.
+    fn group_by_card<T, P, F>(
+        &self,
+        sql: &str,
+        params: P,
+        mut map: F,
+    ) -> Result<HashMap<String, Vec<T>>, ApiError>
+    where
+        P: Params,
+        F: FnMut(&Row<'_>) -> Result<T, rusqlite::Error>,
+    {
+        let mut statement = self.connection.prepare(sql)?;
+        let mut rows = statement.query(params)?;
+        let mut grouped: HashMap<String, Vec<T>> = HashMap::new();
+
+        while let Some(row) = rows.next()? {
+            grouped.entry(row.get(0)?).or_default().push(map(row)?);
+        }
+
+        Ok(grouped)
+    }
+}
+
+/// Columns of a card which are stored in the cards table itself.
+struct CardRow {
+    id: String,
+    name: String,
+    supertype: String,
+    hp: Option<String>,
+    evolves_from: Option<String>,
+    ancient_trait_name: Option<String>,
+    ancient_trait_text: Option<String>,
+    converted_retreat_cost: Option<u32>,
+    set_id: String,
+    number: String,
+    artist: Option<String>,
+    rarity: Option<String>,
+    flavor_text: Option<String>,
+    regulations_mark: Option<String>,
+    images: Image,
+    legalities: Legalities,
+}
+
+static SET_COLUMNS: &str = "SELECT id, name, series, printed_total, total, ptcgo_code, release_date, updated_at, standard_legality, unlimited_legality, expanded_legality FROM sets";
+
+fn read_set(row: &Row<'_>) -> Result<Set, rusqlite::Error> {
+    Ok(Set {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        series: row.get(2)?,
+        printed_total: row.get(3)?,
+        total: row.get(4)?,
+        ptcgo_code: row.get(5)?,
+        release_date: row.get(6)?,
+        updated_at: row.get(7)?,
+        legalities: Legalities {
+            standard: row.get(8)?,
+            unlimited: row.get(9)?,
+            expanded: row.get(10)?,
+        },
+    })
+}
+
+fn schema_version(connection: &Connection) -> Result<u32, ApiError> {
+    Ok(connection.pragma_query_value(None, "user_version", |row| row.get(0))?)
+}
+
+/// Applies every migration newer than the schema version of the database, each in its own transaction.
+fn migrate(connection: &mut Connection) -> Result<(), ApiError> {
+    let version = schema_version(connection)? as usize;
+
+    if version > MIGRATIONS.len() {
+        return Err(ApiError::General(format!(
+            "Database schema version {version} is newer than the supported version {}",
+            MIGRATIONS.len()
+        )));
+    }
+
+    for (index, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+        let transaction = connection.transaction()?;
+        transaction.execute_batch(migration)?;
+        transaction.pragma_update(None, "user_version", index + 1)?;
+        transaction.commit()?;
+    }
+
+    Ok(())
+}
+
+fn upsert_set(transaction: &Transaction<'_>, set: &Set) -> Result<(), ApiError> {
+    transaction.execute(
+        "INSERT INTO sets (id, name, series, printed_total, total, ptcgo_code, release_date, updated_at, standard_legality, unlimited_legality, expanded_legality)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)
+        ON CONFLICT (id) DO UPDATE SET name = excluded.name, series = excluded.series, printed_total = excluded.printed_total,
+            total = excluded.total, ptcgo_code = excluded.ptcgo_code, release_date = excluded.release_date,
+            updated_at = excluded.updated_at, standard_legality = excluded.standard_legality,
+            unlimited_legality = excluded.unlimited_legality, expanded_legality = excluded.expanded_legality
+        WHERE excluded.updated_at >= sets.updated_at",
+        params![
+            set.id,
+            set.name,
+            set.series,
+            set.printed_total,
+            set.total,
+            set.ptcgo_code,
+            set.release_date,
+            set.updated_at,
+            set.legalities.standard,
+            set.legalities.unlimited,
+            set.legalities.expanded,
+        ],
+    )?;
+
+    Ok(())
+}
+
+fn upsert_card(transaction: &Transaction<'_>, card: &Card) -> Result<(), ApiError> {
+    transaction.execute(
+        "INSERT INTO cards (id, name, supertype, hp, evolves_from, ancient_trait_name, ancient_trait_text, converted_retreat_cost, set_id, number, artist, rarity, flavor_text, regulation_mark, image_small, image_large, standard_legality, unlimited_legality, expanded_legality)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19)
+        ON CONFLICT (id) DO UPDATE SET name = excluded.name, supertype = excluded.supertype, hp = excluded.hp,
+            evolves_from = excluded.evolves_from, ancient_trait_name = excluded.ancient_trait_name,
+            ancient_trait_text = excluded.ancient_trait_text, converted_retreat_cost = excluded.converted_retreat_cost,
+            set_id = excluded.set_id, number = excluded.number, artist = excluded.artist, rarity = excluded.rarity,
+            flavor_text = excluded.flavor_text, regulation_mark = excluded.regulation_mark,
+            image_small = excluded.image_small, image_large = excluded.image_large,
+            standard_legality = excluded.standard_legality, unlimited_legality = excluded.unlimited_legality,
+            expanded_legality = excluded.expanded_legality",
+        params![
+            card.id,
+            card.name,
+            card.supertype,
+            card.hp,
+            card.evolves_from,
+            card.ancient_trait.as_ref().map(|ancient_trait| &ancient_trait.name),
+            card.ancient_trait.as_ref().map(|ancient_trait| &ancient_trait.text),
+            card.converted_retreat_cost,
+            card.set.id,
+            card.number,
+            card.artist,
+            card.rarity,
+            card.flavor_text,
+            card.regulations_mark,
+            card.images.small,
+            card.images.large,
+            card.legalities.standard,
+            card.legalities.unlimited,
+            card.legalities.expanded,
+        ],
+    )?;
+
+    for table in CARD_CHILD_TABLES {
+        transaction.execute(
+            &format!("DELETE FROM {table} WHERE card_id = ?1"),
+            [&card.id],
+        )?;
+    }
+
+    insert_list(
+        transaction,
+        "card_subtypes",
+        "subtype",
+        &card.id,
+        card.subtypes.iter().flatten().cloned(),
+    )?;
+    insert_list(
+        transaction,
+        "card_types",
+        "type",
+        &card.id,
+        enum_names(card.types.iter().flatten())?,
+    )?;
+    insert_list(
+        transaction,
+        "card_evolves_to",
+        "name",
+        &card.id,
+        card.evolves_to.iter().flatten().cloned(),
+    )?;
+    insert_list(
+        transaction,
+        "card_rules",
+        "rule",
+        &card.id,
+        card.rules.iter().flatten().cloned(),
+    )?;
+    insert_list(
+        transaction,
+        "card_retreat_costs",
+        "type",
+        &card.id,
+        enum_names(card.retreat_cost.iter().flatten())?,
+    )?;
+
+    for (position, number) in card.national_pokedex_numbers.iter().flatten().enumerate() {
+        transaction.execute(
+            "INSERT INTO card_national_pokedex_numbers (card_id, position, number) VALUES (?1, ?2, ?3)",
+            params![card.id, position, number],
+        )?;
+    }
+
+    for (position, attack) in card.attacks.iter().flatten().enumerate() {
+        transaction.execute(
+            "INSERT INTO attacks (card_id, position, name, converted_energy_cost, damage, text) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![card.id, position, attack.name, attack.converted_energy_cost, attack.damage, attack.text],
+        )?;
+
+        for (cost_position, cost) in enum_names(attack.cost.iter())?.into_iter().enumerate() {
+            transaction.execute(
+                "INSERT INTO attack_costs (card_id, attack_position, position, type) VALUES (?1, ?2, ?3, ?4)",
+                params![card.id, position, cost_position, cost],
+            )?;
+        }
+    }
+
+    for (position, ability) in card.abilities.iter().flatten().enumerate() {
+        transaction.execute(
+            "INSERT INTO abilities (card_id, position, name, text, type) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![card.id, position, ability.name, ability.text, ability.ability_type],
+        )?;
+    }
+
+    for (position, weakness) in card.weaknesses.iter().flatten().enumerate() {
+        transaction.execute(
+            "INSERT INTO weaknesses (card_id, position, type, value) VALUES (?1, ?2, ?3, ?4)",
+            params![
+                card.id,
+                position,
+                enum_name(&weakness.weakness_type)?,
+                weakness.value
+            ],
+        )?;
+    }
+
+    for (position, resistance) in card.resistances.iter().flatten().enumerate() {
+        transaction.execute(
+            "INSERT INTO resistances (card_id, position, type, value) VALUES (?1, ?2, ?3, ?4)",
+            params![
+                card.id,
+                position,
+                enum_name(&resistance.resistance_type)?,
+                resistance.value
+            ],
+        )?;
+    }
+
+    if let Some(tcgplayer) = &card.tcgplayer {
+        transaction.execute(
+            "INSERT INTO tcgplayer (card_id, url, updated_at) VALUES (?1, ?2, ?3)",
+            params![card.id, tcgplayer.url, tcgplayer.updated_at],
+        )?;
+
+        if let Some(prices) = &tcgplayer.prices {
+            let variants = [
+                ("normal", &prices.normal),
+                ("holofoil", &prices.holofoil),
+                ("reverseHolofoil", &prices.reverse_holofoil),
+                ("1stEditionHolofoil", &prices.first_edition_holofoil),
+                ("1stEditionNormal", &prices.first_edition_normal),
+            ];
+
+            for (variant, price) in variants {
+                if let Some(price) = price {
+                    transaction.execute(
+                        "INSERT INTO tcgplayer_prices (card_id, variant, low, mid, high, market, direct_low) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                        params![card.id, variant, price.low, price.mid, price.high, price.market, price.direct_low],
+                    )?;
+                }
+            }
+        }
+    }
+
+    if let Some(cardmarket) = &card.cardmarket {
+        let prices = &cardmarket.prices;
+
+        transaction.execute(
+            "INSERT INTO cardmarket (card_id, url, updated_at, average_sell_price, low_price, trend_price, german_pro_low, suggested_price, reverse_holo_sell, reverse_holo_low, reverse_holo_trend, low_price_ex_plus, avg1, avg7, avg30, reverse_holo_avg1, reverse_holo_avg7, reverse_holo_avg30)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18)",
+            params![
+                card.id,
+                cardmarket.url,
+                cardmarket.updated_at,
+                prices.average_sell_price,
+                prices.low_price,
+                prices.trend_price,
+                prices.german_pro_low,
+                prices.suggested_price,
+                prices.reverse_holo_sell,
+                prices.reverse_holo_low,
+                prices.reverse_holo_trend,
+                prices.low_price_ex_plus,
+                prices.average_day,
+                prices.average_week,
+                prices.average_month,
+                prices.reverse_holo_avg1,
+                prices.reverse_holo_avg7,
+                prices.reverse_holo_avg30,
+            ],
+        )?;
+    }
+
+    Ok(())
+}
+
+fn insert_list(
+    transaction: &Transaction<'_>,
+    table: &str,
+    column: &str,
+    card_id: &str,
+    values: impl IntoIterator<Item = String>,
+) -> Result<(), ApiError> {
+    for (position, value) in values.into_iter().enumerate() {
+        transaction.execute(
+            &format!("INSERT INTO {table} (card_id, position, {column}) VALUES (?1, ?2, ?3)"),
+            params![card_id, position, value],
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Returns the api name of an enum value, e.g. `Fire` for `PokemonType::Fire`.
+fn enum_name<T: Serialize>(value: &T) -> Result<String, ApiError> {
+    match serde_json::to_value(value)? {
+        Value::String(name) => Ok(name),
+        other => Err(ApiError::General(format!("{other} is no enum name"))),
+    }
+}
+
+fn enum_names<'a, T: Serialize + 'a>(
+    values: impl Iterator<Item = &'a T>,
+) -> Result<Vec<String>, ApiError> {
+    values.map(enum_name).collect()
+}
+
+fn parse_enum<T: DeserializeOwned>(name: &str) -> Result<T, ApiError> {
+    Ok(serde_json::from_value(Value::String(name.to_owned()))?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn full_card() -> Card {
+        serde_json::from_str(
+            r#"{
+            "id": "swsh4-25",
+            "name": "Charizard",
+            "supertype": "Pokémon",
+            "subtypes": ["Stage 2"],
+            "hp": "170",
+            "types": ["Fire"],
+            "evolvesFrom": "Charmeleon",
+            "evolvesTo": ["Charizard VMAX"],
+            "rules": ["Rule one", "Rule two"],
+            "abilities": [{ "name": "Blaze", "text": "Burns.", "type": "Ability" }],
+            "ancientTrait": { "name": "Theta Stop", "text": "Prevents effects." },
+            "attacks": [
+                {
+                    "name": "Royal Blaze",
+                    "cost": ["Fire", "Fire"],
+                    "convertedEnergyCost": 2,
+                    "damage": "100+",
+                    "text": "Does more damage."
+                },
+                {
+                    "name": "Scratch",
+                    "cost": ["Colorless"],
+                    "convertedEnergyCost": 1,
+                    "damage": "20",
+                    "text": ""
+                }
+            ],
+            "weaknesses": [{ "type": "Water", "value": "×2" }],
+            "resistances": [{ "type": "Fighting", "value": "-30" }],
+            "retreatCost": ["Colorless", "Colorless", "Colorless"],
+            "convertedRetreatCost": 3,
+            "set": {
+                "id": "swsh4",
+                "name": "Vivid Voltage",
+                "series": "Sword & Shield",
+                "printedTotal": 185,
+                "total": 203,
+                "legalities": { "standard": "Legal", "unlimited": "Legal", "expanded": "Legal" },
+                "ptcgoCode": "VIV",
+                "releaseDate": "2020/11/13",
+                "updatedAt": "2020/11/13 16:20:00"
+            },
+            "number": "25",
+            "artist": "Ryuta Fuse",
+            "rarity": "Rare",
+            "flavorText": "It spits fire.",
+            "nationalPokedexNumbers": [6],
+            "legalities": { "standard": "Legal", "unlimited": "Legal", "expanded": "Legal" },
+            "regulationMark": "D",
+            "images": { "small": "small.png", "large": "large.png" },
+            "tcgplayer": {
+                "url": "https://prices.pokemontcg.io/tcgplayer/swsh4-25",
+                "updatedAt": "2021/08/04",
+                "prices": {
+                    "normal": { "low": 1.0, "mid": 2.5, "high": 5.0, "market": 2.0, "directLow": 1.5 },
+                    "holofoil": { "low": 3.0, "mid": 4.0, "high": 9.5, "market": 4.5, "directLow": 3.5 },
+                    "reverseHolofoil": { "low": 2.0, "mid": 3.0, "high": 6.0, "market": 3.5, "directLow": null },
+                    "1stEditionHolofoil": { "low": 10.0, "mid": 20.0, "high": 30.0, "market": 25.0, "directLow": 15.0 },
+                    "1stEditionNormal": { "low": 5.0, "mid": 6.0, "high": 7.0, "market": 6.5, "directLow": 5.5 }
+                }
+            },
+            "cardmarket": {
+                "url": "https://prices.pokemontcg.io/cardmarket/swsh4-25",
+                "updatedAt": "2021/08/04",
+                "prices": {
+                    "averageSellPrice": 9.38,
+                    "lowPrice": 8.95,
+                    "trendPrice": 10.29,
+                    "germanProLow": 1.0,
+                    "suggestedPrice": 2.0,
+                    "reverseHoloSell": 3.0,
+                    "reverseHoloLow": 4.0,
+                    "reverseHoloTrend": 5.0,
+                    "lowPriceExPlus": 8.95,
+                    "avg1": 9.95,
+                    "avg7": 9.35,
+                    "avg30": 8.72,
+                    "reverseHoloAvg1": 6.0,
+                    "reverseHoloAvg7": 7.0,
+                    "reverseHoloAvg30": 8.0
+                }
+            }
+        }"#,
+        )
+        .unwrap()
+    }
+
+    fn row_count(store: &SqliteStore, table: &str) -> usize {
+        store
+            .connection()
+            .query_row(&format!("SELECT COUNT(*) FROM {table}"), [], |row| {
+                row.get(0)
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn reads_back_upserted_card() {
+        let mut store = SqliteStore::open_in_memory().unwrap();
+        let card = full_card();
+
+        store.upsert_cards(std::slice::from_ref(&card)).unwrap();
+
+        let stored = store.get_card(&CardId(card.id.clone())).unwrap().unwrap();
+        assert_eq!(
+            serde_json::to_value(&stored).unwrap(),
+            serde_json::to_value(&card).unwrap()
+        );
+
+        let set = store.get_set(&SetId(card.set.id.clone())).unwrap().unwrap();
+        assert_eq!(
+            serde_json::to_value(&set).unwrap(),
+            serde_json::to_value(&card.set).unwrap()
+        );
+    }
+
+    #[test]
+    fn reupsert_replaces_child_rows() {
+        let mut store = SqliteStore::open_in_memory().unwrap();
+        let mut card = full_card();
+
+        store.upsert_cards(std::slice::from_ref(&card)).unwrap();
+
+        card.attacks = card.attacks.map(|attacks| attacks[1..].to_vec());
+        card.retreat_cost = None;
+        if let Some(prices) = card
+            .tcgplayer
+            .as_mut()
+            .and_then(|tcgplayer| tcgplayer.prices.as_mut())
+        {
+            prices.holofoil = None;
+            prices.reverse_holofoil = None;
+            prices.first_edition_holofoil = None;
+            prices.first_edition_normal = None;
+        }
+        if let Some(cardmarket) = card.cardmarket.as_mut() {
+            cardmarket.prices.trend_price = Some(12.5);
+        }
+
+        store.upsert_cards(std::slice::from_ref(&card)).unwrap();
+
+        let stored = store.get_card(&CardId(card.id.clone())).unwrap().unwrap();
+        assert_eq!(
+            serde_json::to_value(&stored).unwrap(),
+            serde_json::to_value(&card).unwrap()
+        );
+        assert_eq!(row_count(&store, "attacks"), 1);
+        assert_eq!(row_count(&store, "attack_costs"), 1);
+        assert_eq!(row_count(&store, "card_retreat_costs"), 0);
+        assert_eq!(row_count(&store, "tcgplayer_prices"), 1);
+        assert_eq!(row_count(&store, "cards"), 1);
+    }
+
+    #[test]
+    fn rejects_newer_schema_version() {
+        let connection = Connection::open_in_memory().unwrap();
+        connection
+            .pragma_update(None, "user_version", MIGRATIONS.len() + 1)
+            .unwrap();
+
+        assert!(SqliteStore::from_connection(connection).is_err());
+    }
+
+    #[test]
+    fn migrates_to_current_schema_version() {
+        let store = SqliteStore::open_in_memory().unwrap();
+
+        assert_eq!(store.schema_version().unwrap() as usize, MIGRATIONS.len());
+    }
+}
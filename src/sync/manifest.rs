@@ -0,0 +1,95 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::pokemon_api_client::{disk_cache::write_file_atomically, error::ApiError};
+
+/// Version of the snapshot layout, increased on incompatible changes.
+pub static SNAPSHOT_VERSION: u32 = 1;
+
+/// Manifest describing the content and freshness of a snapshot directory.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SnapshotManifest {
+    /// The version of the snapshot layout.
+    pub version: u32,
+    /// The unix timestamp in seconds of the first sync into the directory.
+    pub created_at: u64,
+    /// The unix timestamp in seconds of the last completed sync.
+    pub synced_at: u64,
+    /// The amount of sets in the snapshot.
+    pub set_count: usize,
+    /// The amount of cards in the snapshot.
+    pub card_count: usize,
+    /// The synced sets by their id.
+    pub sets: BTreeMap<String, SyncedSet>,
+}
+
+/// Sync state of a single set in the snapshot.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SyncedSet {
+    /// The `updatedAt` value of the set at the time its cards were synced.
+    pub updated_at: String,
+    /// The amount of cards of the set in the snapshot.
+    pub card_count: usize,
+    /// The unix timestamp in seconds of when the cards of the set were synced.
+    pub synced_at: u64,
+}
+
+/// Lists of the metadata endpoints of the api stored in the snapshot.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SnapshotMetadata {
+    pub types: Vec<String>,
+    pub subtypes: Vec<String>,
+    pub supertypes: Vec<String>,
+    pub rarities: Vec<String>,
+}
+
+impl SnapshotManifest {
+    /// Loads the manifest of a snapshot directory.
+    ///
+    /// # Arguments
+    ///
+    /// * `directory` - The snapshot directory.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the manifest does not exist or cannot be parsed.
+    pub fn load(directory: &Path) -> Result<Self, ApiError> {
+        read_json(&manifest_path(directory))
+    }
+}
+
+pub(crate) fn manifest_path(directory: &Path) -> PathBuf {
+    directory.join("manifest.json")
+}
+
+pub(crate) fn metadata_path(directory: &Path) -> PathBuf {
+    directory.join("metadata.json")
+}
+
+pub(crate) fn sets_path(directory: &Path) -> PathBuf {
+    directory.join("sets.json")
+}
+
+pub(crate) fn cards_directory(directory: &Path) -> PathBuf {
+    directory.join("cards")
+}
+
+pub(crate) fn cards_path(directory: &Path, set_id: &str) -> PathBuf {
+    cards_directory(directory).join(format!("{set_id}.json"))
+}
+
+pub(crate) fn read_json<T: DeserializeOwned>(path: &Path) -> Result<T, ApiError> {
+    let content = fs::read(path)?;
+
+    Ok(serde_json::from_slice(&content)?)
+}
+
+/// Writes the json through a unique temporary file, so readers never see a half written file.
+pub(crate) fn write_json<T: Serialize>(path: &Path, value: &T) -> Result<(), ApiError> {
+    write_file_atomically(path, &serde_json::to_vec(value)?)
+}
@@ -0,0 +1,511 @@
+//! Contains the synchronisation of the whole catalogue into a local snapshot directory.
+//!
+//! A snapshot directory contains:
+//! - `manifest.json` with the timestamps and counts of the snapshot
+//! - `metadata.json` with the types, subtypes, supertypes and rarities
+//! - `sets.json` with every set
+//! - `cards/<set id>.json` with the cards of every set
+pub mod manifest;
+
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use futures::{stream, StreamExt};
+
+use crate::{
+    builder::{pokemon::PokemonQueryBuilder, QueryBuilder},
+    pokemon_api_client::{
+        api_client::{PokemonApiClient, SetId},
+        disk_cache::unix_now,
+        error::ApiError,
+        transport::{ReqwestTransport, Transport},
+    },
+    resource::{card::Card, set::Set},
+};
+
+use self::manifest::{
+    cards_directory, cards_path, metadata_path, sets_path, write_json, SnapshotManifest,
+    SnapshotMetadata, SyncedSet, SNAPSHOT_VERSION,
+};
+
+/// Downloads every set and card into a snapshot directory.
+/// Subsequent runs only refetch the cards of sets whose `updated_at` changed since the last run.
+pub struct CatalogueSync<Tr = ReqwestTransport> {
+    client: PokemonApiClient<Tr>,
+    directory: PathBuf,
+    max_concurrency: usize,
+    full_refresh: bool,
+}
+
+/// Summary of a sync run.
+#[derive(Debug)]
+pub struct SyncReport {
+    /// The ids of the sets whose cards were fetched.
+    pub updated_sets: Vec<String>,
+    /// The ids of the sets which no longer exist in the api and were removed.
+    pub removed_sets: Vec<String>,
+    /// The amount of sets which were up to date.
+    pub unchanged_set_count: usize,
+    /// The sets whose cards could not be fetched, they are retried on the next run.
+    pub failed_sets: Vec<SetFailure>,
+    /// The manifest written by the run.
+    pub manifest: SnapshotManifest,
+}
+
+/// A set whose cards could not be fetched during a sync run.
+#[derive(Debug)]
+pub struct SetFailure {
+    /// The id of the set.
+    pub set_id: String,
+    /// The error which occured while fetching the cards.
+    pub error: ApiError,
+}
+
+impl<Tr: Transport> CatalogueSync<Tr> {
+    /// Creates a new sync into the given snapshot directory.
+    ///
+    /// # Arguments
+    ///
+    /// * `client` - The client used to query the api.
+    /// * `directory` - The snapshot directory, created if it does not exist.
+    #[must_use]
+    pub fn new(client: PokemonApiClient<Tr>, directory: impl Into<PathBuf>) -> Self {
+        CatalogueSync {
+            client,
+            directory: directory.into(),
+            max_concurrency: 4,
+            full_refresh: false,
+        }
+    }
+
+    /// Sets the maximum amount of requests sent at the same time.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_concurrency` - The maximum amount of concurrent requests.
+    #[must_use]
+    pub fn with_max_concurrency(mut self, max_concurrency: usize) -> Self {
+        self.max_concurrency = max_concurrency.max(1);
+        self
+    }
+
+    /// Sets whether the cards of every set are fetched again, regardless of their `updated_at`.
+    ///
+    /// # Arguments
+    ///
+    /// * `full_refresh` - Whether to refetch every set.
+    #[must_use]
+    pub fn with_full_refresh(mut self, full_refresh: bool) -> Self {
+        self.full_refresh = full_refresh;
+        self
+    }
+
+    /// Returns the snapshot directory of the sync.
+    #[must_use]
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    /// Runs the sync, fetching the metadata, all sets and the cards of every outdated set.
+    /// The manifest is written last, so an interrupted run is continued by the next one.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the metadata or the list of sets cannot be fetched completely or the snapshot cannot be written.
+    /// Will return `ApiError::Cancelled` without writing anything if the cancellation token of the client is cancelled.
+    /// Sets whose cards cannot be fetched are reported in the `failed_sets` of the report instead.
+    pub async fn run(&self) -> Result<SyncReport, ApiError> {
+        fs::create_dir_all(cards_directory(&self.directory))?;
+
+        let previous = SnapshotManifest::load(&self.directory)
+            .ok()
+            .filter(|manifest| manifest.version == SNAPSHOT_VERSION);
+        let previous_sets = previous
+            .as_ref()
+            .map(|manifest| manifest.sets.clone())
+            .unwrap_or_default();
+
+        let metadata = SnapshotMetadata {
+            types: self.client.get_all_types().await?,
+            subtypes: self.client.get_all_subtype().await?,
+            supertypes: self.client.get_all_supertypes().await?,
+            rarities: self.client.get_all_rarities().await?,
+        };
+
+        let all_sets = self
+            .client
+            .get_all_sets_exhaustive(self.max_concurrency)
+            .await?;
+        if let Some(failure) = all_sets.failed_pages.into_iter().next() {
+            return Err(failure.error);
+        }
+        let sets = all_sets.data;
+
+        let outdated_sets = sets
+            .iter()
+            .filter(|set| self.is_outdated(set, previous_sets.get(&set.id)))
+            .collect::<Vec<&Set>>();
+
+        let fetched = stream::iter(outdated_sets)
+            .map(|set| async move { (set, self.fetch_cards(&set.id).await) })
+            .buffer_unordered(self.max_concurrency)
+            .collect::<Vec<(&Set, Result<Vec<Card>, ApiError>)>>()
+            .await;
+
+        if fetched
+            .iter()
+            .any(|(_, result)| matches!(result, Err(ApiError::Cancelled)))
+        {
+            return Err(ApiError::Cancelled);
+        }
+
+        let now = unix_now();
+        let mut synced_sets = BTreeMap::new();
+        let mut updated_sets = Vec::new();
+        let mut failed_sets = Vec::new();
+
+        for (set, result) in fetched {
+            match result {
+                Ok(cards) => {
+                    write_json(&cards_path(&self.directory, &set.id), &cards)?;
+                    synced_sets.insert(
+                        set.id.clone(),
+                        SyncedSet {
+                            updated_at: set.updated_at.clone(),
+                            card_count: cards.len(),
+                            synced_at: now,
+                        },
+                    );
+                    updated_sets.push(set.id.clone());
+                }
+                Err(error) => failed_sets.push(SetFailure {
+                    set_id: set.id.clone(),
+                    error,
+                }),
+            }
+        }
+
+        let failed_set_ids = failed_sets
+            .iter()
+            .map(|failure| failure.set_id.clone())
+            .collect::<HashSet<String>>();
+
+        let mut unchanged_set_count = 0;
+        for set in &sets {
+            if synced_sets.contains_key(&set.id) {
+                continue;
+            }
+            if let Some(previous_set) = previous_sets.get(&set.id) {
+                // Failed sets keep their previous cards, but are reported as failed only.
+                if previous_set.updated_at == set.updated_at && !failed_set_ids.contains(&set.id) {
+                    unchanged_set_count += 1;
+                }
+                synced_sets.insert(set.id.clone(), previous_set.clone());
+            }
+        }
+
+        let set_ids = sets
+            .iter()
+            .map(|set| set.id.as_str())
+            .collect::<HashSet<&str>>();
+        let mut removed_sets = Vec::new();
+        for set_id in previous_sets.keys() {
+            if !set_ids.contains(set_id.as_str()) {
+                match fs::remove_file(cards_path(&self.directory, set_id)) {
+                    Err(err) if err.kind() != std::io::ErrorKind::NotFound => {
+                        return Err(err.into())
+                    }
+                    _ => removed_sets.push(set_id.clone()),
+                }
+            }
+        }
+
+        write_json(&metadata_path(&self.directory), &metadata)?;
+        write_json(&sets_path(&self.directory), &sets)?;
+
+        let manifest = SnapshotManifest {
+            version: SNAPSHOT_VERSION,
+            created_at: previous.map_or(now, |manifest| manifest.created_at),
+            synced_at: now,
+            set_count: sets.len(),
+            card_count: synced_sets.values().map(|set| set.card_count).sum(),
+            sets: synced_sets,
+        };
+        write_json(&manifest::manifest_path(&self.directory), &manifest)?;
+
+        Ok(SyncReport {
+            updated_sets,
+            removed_sets,
+            unchanged_set_count,
+            failed_sets,
+            manifest,
+        })
+    }
+
+    fn is_outdated(&self, set: &Set, previous: Option<&SyncedSet>) -> bool {
+        self.full_refresh
+            || previous.is_none_or(|previous| previous.updated_at != set.updated_at)
+            || !cards_path(&self.directory, &set.id).exists()
+    }
+
+    async fn fetch_cards(&self, set_id: &str) -> Result<Vec<Card>, ApiError> {
+        let query_builder = PokemonQueryBuilder::new().add_set_id(&SetId(set_id.to_owned()));
+        let all_cards = self
+            .client
+            .get_all_queryable_resources(query_builder, 1)
+            .await?;
+
+        match all_cards.failed_pages.into_iter().next() {
+            Some(failure) => Err(failure.error),
+            None => Ok(all_cards.data),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::process;
+
+    use tokio_util::sync::CancellationToken;
+
+    use super::*;
+    use crate::pokemon_api_client::transport::{
+        MemoryTransport, TransportRequest, TransportResponse,
+    };
+
+    static BASE_URL: &str = "https://api.test/v2";
+
+    /// Cancels the token as soon as a request for the url is sent.
+    struct CancellingTransport {
+        inner: MemoryTransport,
+        token: CancellationToken,
+        cancel_on: String,
+    }
+
+    impl Transport for CancellingTransport {
+        async fn send(&self, request: TransportRequest) -> Result<TransportResponse, ApiError> {
+            if request.url == self.cancel_on {
+                self.token.cancel();
+            }
+
+            self.inner.send(request).await
+        }
+    }
+
+    fn snapshot_directory(name: &str) -> PathBuf {
+        let directory =
+            std::env::temp_dir().join(format!("pokemon-tcg-sync-{name}-{}", process::id()));
+        let _ = fs::remove_dir_all(&directory);
+
+        directory
+    }
+
+    fn cards_url(set_id: &str) -> String {
+        format!("{BASE_URL}/cards?pageSize=250&page=1&q=set.id%3A{set_id}")
+    }
+
+    fn set_json(id: &str, updated_at: &str) -> String {
+        format!(
+            r#"{{
+                "id": "{id}", "name": "Set {id}", "series": "Base", "printedTotal": 2, "total": 2,
+                "legalities": {{ "unlimited": "Legal" }},
+                "releaseDate": "1999/01/09", "updatedAt": "{updated_at}"
+            }}"#
+        )
+    }
+
+    fn cards_json(set_id: &str, names: &[&str]) -> String {
+        let data = names
+            .iter()
+            .enumerate()
+            .map(|(index, name)| {
+                format!(
+                    r#"{{
+                        "id": "{set_id}-{index}", "name": "{name}", "supertype": "Pokémon",
+                        "set": {},
+                        "number": "{index}",
+                        "legalities": {{ "unlimited": "Legal" }},
+                        "images": {{ "small": "small.png", "large": "large.png" }}
+                    }}"#,
+                    set_json(set_id, "2020/01/01 00:00:00")
+                )
+            })
+            .collect::<Vec<String>>()
+            .join(",");
+
+        format!(
+            r#"{{"data":[{data}],"page":1,"pageSize":250,"count":{0},"totalCount":{0}}}"#,
+            names.len()
+        )
+    }
+
+    fn insert_sets(transport: &MemoryTransport, sets: &[(&str, &str)]) {
+        let data = sets
+            .iter()
+            .map(|(id, updated_at)| set_json(id, updated_at))
+            .collect::<Vec<String>>()
+            .join(",");
+
+        transport.insert(
+            &format!("{BASE_URL}/sets?pageSize=250&page=1"),
+            TransportResponse::new(
+                200,
+                format!(
+                    r#"{{"data":[{data}],"page":1,"pageSize":250,"count":{0},"totalCount":{0}}}"#,
+                    sets.len()
+                ),
+            ),
+        );
+    }
+
+    fn transport() -> MemoryTransport {
+        let transport = MemoryTransport::new()
+            .with_json(&format!("{BASE_URL}/types"), r#"{"data":["Fire"]}"#)
+            .with_json(&format!("{BASE_URL}/subtypes"), r#"{"data":["Basic"]}"#)
+            .with_json(&format!("{BASE_URL}/supertypes"), r#"{"data":["Pokémon"]}"#)
+            .with_json(&format!("{BASE_URL}/rarities"), r#"{"data":["Rare"]}"#)
+            .with_json(&cards_url("a"), &cards_json("a", &["Alpha", "Beta"]))
+            .with_json(&cards_url("b"), &cards_json("b", &["Gamma"]))
+            .with_json(&cards_url("c"), &cards_json("c", &["Delta"]));
+        insert_sets(
+            &transport,
+            &[
+                ("a", "2020/01/01 00:00:00"),
+                ("b", "2020/01/01 00:00:00"),
+                ("c", "2020/01/01 00:00:00"),
+            ],
+        );
+
+        transport
+    }
+
+    fn sync<Tr: Transport>(transport: Tr, directory: &Path) -> CatalogueSync<Tr> {
+        let client = PokemonApiClient::builder()
+            .with_base_url(BASE_URL)
+            .build_with_transport(transport);
+
+        CatalogueSync::new(client, directory).with_max_concurrency(1)
+    }
+
+    fn card_requests(transport: &MemoryTransport) -> usize {
+        transport
+            .requests()
+            .iter()
+            .filter(|request| request.url.starts_with(&format!("{BASE_URL}/cards")))
+            .count()
+    }
+
+    #[tokio::test]
+    async fn first_sync_writes_snapshot() {
+        let directory = snapshot_directory("first");
+        let transport = transport();
+
+        let mut report = sync(transport.clone(), &directory).run().await.unwrap();
+        report.updated_sets.sort();
+
+        assert_eq!(report.updated_sets, vec!["a", "b", "c"]);
+        assert_eq!(report.unchanged_set_count, 0);
+        assert!(report.failed_sets.is_empty());
+        assert_eq!(report.manifest.set_count, 3);
+        assert_eq!(report.manifest.card_count, 4);
+
+        let manifest = SnapshotManifest::load(&directory).unwrap();
+        assert_eq!(manifest.sets["a"].card_count, 2);
+        let cards: Vec<Card> = manifest::read_json(&cards_path(&directory, "a")).unwrap();
+        assert_eq!(cards.len(), 2);
+        let metadata: SnapshotMetadata = manifest::read_json(&metadata_path(&directory)).unwrap();
+        assert_eq!(metadata.types, vec!["Fire"]);
+
+        let _ = fs::remove_dir_all(&directory);
+    }
+
+    #[tokio::test]
+    async fn unchanged_sets_are_not_fetched_again() {
+        let directory = snapshot_directory("unchanged");
+        let transport = transport();
+
+        sync(transport.clone(), &directory).run().await.unwrap();
+        let first_card_requests = card_requests(&transport);
+        let report = sync(transport.clone(), &directory).run().await.unwrap();
+
+        assert!(report.updated_sets.is_empty());
+        assert!(report.removed_sets.is_empty());
+        assert_eq!(report.unchanged_set_count, 3);
+        assert_eq!(report.manifest.card_count, 4);
+        assert_eq!(card_requests(&transport), first_card_requests);
+
+        let _ = fs::remove_dir_all(&directory);
+    }
+
+    #[tokio::test]
+    async fn failed_sets_keep_previous_cards() {
+        let directory = snapshot_directory("failed");
+        let transport = transport();
+
+        sync(transport.clone(), &directory).run().await.unwrap();
+
+        insert_sets(
+            &transport,
+            &[
+                ("a", "2020/01/01 00:00:00"),
+                ("b", "2021/01/01 00:00:00"),
+                ("c", "2021/01/01 00:00:00"),
+            ],
+        );
+        transport.insert(
+            &cards_url("b"),
+            TransportResponse::new(400, r#"{"error":{"message":"Bad Request","code":400}}"#),
+        );
+        transport.insert(
+            &cards_url("c"),
+            TransportResponse::new(200, cards_json("c", &["Delta", "Epsilon"])),
+        );
+
+        let report = sync(transport.clone(), &directory).run().await.unwrap();
+
+        assert_eq!(report.updated_sets, vec!["c"]);
+        assert_eq!(report.unchanged_set_count, 1);
+        assert_eq!(report.failed_sets.len(), 1);
+        assert_eq!(report.failed_sets[0].set_id, "b");
+        assert_eq!(report.manifest.sets["b"].updated_at, "2020/01/01 00:00:00");
+        assert_eq!(report.manifest.card_count, 5);
+
+        let cards: Vec<Card> = manifest::read_json(&cards_path(&directory, "b")).unwrap();
+        assert_eq!(cards[0].name, "Gamma");
+
+        let _ = fs::remove_dir_all(&directory);
+    }
+
+    #[tokio::test]
+    async fn cancelled_sync_writes_nothing() {
+        let directory = snapshot_directory("cancelled");
+        let token = CancellationToken::new();
+        let transport = CancellingTransport {
+            inner: transport(),
+            token: token.clone(),
+            cancel_on: cards_url("b"),
+        };
+        let client = PokemonApiClient::builder()
+            .with_base_url(BASE_URL)
+            .build_with_transport(transport)
+            .with_cancellation_token(token);
+
+        let result = CatalogueSync::new(client, &directory)
+            .with_max_concurrency(1)
+            .run()
+            .await;
+
+        assert!(matches!(result, Err(ApiError::Cancelled)));
+        assert!(!manifest::manifest_path(&directory).exists());
+        assert!(!metadata_path(&directory).exists());
+        assert!(fs::read_dir(cards_directory(&directory))
+            .unwrap()
+            .next()
+            .is_none());
+
+        let _ = fs::remove_dir_all(&directory);
+    }
+}